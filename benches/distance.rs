@@ -0,0 +1,63 @@
+// Compare the cost of computing the full edit matrix with the cost
+// of computing the distance only, with 2 rows of costs, or within a bound
+//
+// Run with: cargo bench
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use edits::costs::*;
+use edits::distance::*;
+use edits::edit_matrix::*;
+use edits::myers::*;
+
+// Create a text of n chars and a slightly modified copy of it
+fn texts(n: usize) -> (Vec<char>, Vec<char>) {
+    let text: Vec<char> = "the quick brown fox jumps over the lazy dog "
+        .chars()
+        .cycle()
+        .take(n)
+        .collect();
+    let modified = text
+        .iter()
+        .enumerate()
+        .map(|(i, c)| if i % 50 == 0 { '*' } else { *c })
+        .collect();
+    (text, modified)
+}
+
+fn bench_distance(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distance");
+    group.sample_size(10);
+
+    // the edit matrix and the two rows are compared at the same sizes.
+    // The full matrix for 10k chars would take more than 1Gb of memory, so only the two rows are
+    // benchmarked at that size
+    for n in [1000, 2000, 5000] {
+        let (text1, text2) = texts(n);
+        group.bench_with_input(BenchmarkId::new("edit matrix", n), &n, |b, _| {
+            b.iter(|| create_edit_matrix(&levenshtein_costs(), black_box(&text1), &text2))
+        });
+    }
+    for n in [1000, 2000, 5000, 10000] {
+        let (text1, text2) = texts(n);
+        group.bench_with_input(BenchmarkId::new("two rows", n), &n, |b, _| {
+            b.iter(|| distance(black_box(&text1), &text2))
+        });
+    }
+    // only the cells close to the diagonal are computed when the distance is bounded
+    for n in [1000, 2000, 5000, 10000] {
+        let (text1, text2) = texts(n);
+        group.bench_with_input(BenchmarkId::new("bounded", n), &n, |b, _| {
+            b.iter(|| distance_within(black_box(&text1), &text2, n / 40))
+        });
+    }
+    // the Myers algorithm computes the edit operations in linear memory
+    for n in [1000, 10000, 100000] {
+        let (text1, text2) = texts(n);
+        group.bench_with_input(BenchmarkId::new("myers", n), &n, |b, _| {
+            b.iter(|| myers_edit_operations(black_box(&text1), &text2, &levenshtein_costs()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_distance);
+criterion_main!(benches);
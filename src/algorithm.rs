@@ -0,0 +1,62 @@
+use crate::costs::*;
+use crate::edit_operation::*;
+use crate::myers::*;
+use crate::patience::*;
+use std::hash::Hash;
+use Algorithm::*;
+
+// Algorithm used to compute the edit operations going from one list of tokens to another
+//  - Levenshtein: tokens are inserted, deleted or substituted
+//  - Damerau: additionally 2 adjacent tokens can be transposed, so that a typo like "teh" is a single edit
+//  - Myers: tokens are inserted or deleted, then paired as substitutions.
+//    This only uses linear memory and is fast for large texts with few differences,
+//    so texts are not split in chunks when using this algorithm
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Algorithm {
+    Levenshtein,
+    Damerau,
+    Myers,
+}
+
+// Default algorithm
+pub fn default_algorithm() -> Algorithm {
+    Levenshtein
+}
+
+// Return the list of operations necessary to go from one list of tokens to another
+// using a given algorithm and some given costs
+pub fn algorithm_edit_operations<T: PartialEq + Clone>(
+    algorithm: Algorithm,
+    ts1: &[T],
+    ts2: &[T],
+    costs: &impl Costs<T>,
+) -> Vec<EditOperation<T>> {
+    match algorithm {
+        Levenshtein => edit_operations(ts1, ts2, costs),
+        Damerau => damerau_edit_operations(ts1, ts2, costs),
+        Myers => myers_edit_operations(ts1, ts2, costs),
+    }
+}
+
+// Algorithm used to align the lines of 2 texts, before comparing the tokens of modified lines
+//  - Patience: the lines which are unique in both texts are aligned first
+//  - Histogram: the least frequent common lines are aligned first
+// Both algorithms avoid aligning frequent lines, like lone braces in source code
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LineAlgorithm {
+    Patience,
+    Histogram,
+}
+
+// Return the list of operations necessary to go from one list of lines to another
+// using a given line algorithm
+pub fn line_algorithm_edit_operations<T: Clone + Eq + Hash>(
+    line_algorithm: LineAlgorithm,
+    ts1: &[T],
+    ts2: &[T],
+) -> Vec<EditOperation<T>> {
+    match line_algorithm {
+        LineAlgorithm::Patience => patience_edit_operations(ts1, ts2),
+        LineAlgorithm::Histogram => histogram_edit_operations(ts1, ts2),
+    }
+}
@@ -1,4 +1,14 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::renderer::*;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::sync::Arc;
+use Color::*;
+
+// Colors which can be displayed in a terminal:
+//  - the 8 basic colors and their bright variants
+//  - Ansi256(n): one of the 256 colors of the extended palette
+//  - Rgb(r, g, b): a 24-bit color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
     Black,
     Red,
@@ -8,9 +18,78 @@ pub enum Color {
     Magenta,
     Cyan,
     White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
 }
 
-use Color::*;
+// Style used to display some text in a terminal: foreground and background colors and attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub inverse: bool,
+}
+
+// Style displaying the text as it is
+pub fn plain_style() -> Style {
+    Style {
+        foreground: None,
+        background: None,
+        bold: false,
+        underline: false,
+        strikethrough: false,
+        inverse: false,
+    }
+}
+
+// Style only setting the color of the text
+pub fn color_style(c: Color) -> Style {
+    Style {
+        foreground: Some(c),
+        ..plain_style()
+    }
+}
+
+impl Style {
+    // Return the escape sequence setting this style, or an empty string for the plain style
+    pub fn escape_sequence(&self) -> String {
+        let mut codes: Vec<String> = vec![];
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.underline {
+            codes.push("4".to_string());
+        }
+        if self.inverse {
+            codes.push("7".to_string());
+        }
+        if self.strikethrough {
+            codes.push("9".to_string());
+        }
+        if let Some(c) = self.foreground {
+            codes.push(code(c, 30));
+        }
+        if let Some(c) = self.background {
+            codes.push(code(c, 40));
+        }
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
 
 pub trait ColorAs {
     fn color_as(self, c: Color) -> String;
@@ -19,7 +98,7 @@ pub trait ColorAs {
 impl ColorAs for String {
     // Surround a string with ASCII control characters to color it
     fn color_as(self, c: Color) -> String {
-        format!("\x1b[{}m{}\x1b[0m", code(c), self)
+        self.style_as(color_style(c))
     }
 }
 
@@ -30,18 +109,102 @@ impl ColorAs for char {
     }
 }
 
-fn code(c: Color) -> String {
+pub trait StyleAs {
+    fn style_as(self, s: Style) -> String;
+}
+
+impl StyleAs for String {
+    // Surround a string with ASCII control characters to style it.
+    // The string is left unchanged with the plain style
+    fn style_as(self, s: Style) -> String {
+        let sequence = s.escape_sequence();
+        if sequence.is_empty() {
+            self
+        } else {
+            format!("{}{}\x1b[0m", sequence, self)
+        }
+    }
+}
+
+impl StyleAs for char {
+    // Surround a char with ASCII control characters to style it
+    fn style_as(self, s: Style) -> String {
+        self.to_string().style_as(s)
+    }
+}
+
+// Return the code of a color, where base is 30 for a foreground color and 40 for a background color
+fn code(c: Color, base: u8) -> String {
     match c {
-        Black => "30",
-        Red => "31",
-        Green => "32",
-        Yellow => "33",
-        Blue => "34",
-        Magenta => "35",
-        Cyan => "36",
-        White => "37",
+        Black => base.to_string(),
+        Red => (base + 1).to_string(),
+        Green => (base + 2).to_string(),
+        Yellow => (base + 3).to_string(),
+        Blue => (base + 4).to_string(),
+        Magenta => (base + 5).to_string(),
+        Cyan => (base + 6).to_string(),
+        White => (base + 7).to_string(),
+        BrightBlack => (base + 60).to_string(),
+        BrightRed => (base + 61).to_string(),
+        BrightGreen => (base + 62).to_string(),
+        BrightYellow => (base + 63).to_string(),
+        BrightBlue => (base + 64).to_string(),
+        BrightMagenta => (base + 65).to_string(),
+        BrightCyan => (base + 66).to_string(),
+        BrightWhite => (base + 67).to_string(),
+        Ansi256(n) => format!("{};5;{}", base + 8, n),
+        Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
+
+// When to display colors:
+//  - Always / Never: regardless of the environment
+//  - Auto: only when the output is a terminal, unless the NO_COLOR, CLICOLOR or CLICOLOR_FORCE
+//    environment variables say otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Always,
+    Never,
+    Auto,
+}
+
+// Return true if colors must be displayed for a given choice, when writing to some output,
+// for example std::io::stderr() for assertion messages
+pub fn use_colors(choice: ColorChoice, output: &impl IsTerminal) -> bool {
+    colors_enabled(choice, output.is_terminal(), |name| std::env::var_os(name))
+}
+
+// Return the renderer to use for a given choice when writing to some output: colored or with brackets only
+pub fn color_choice_renderer(
+    choice: ColorChoice,
+    output: &impl IsTerminal,
+) -> Arc<dyn DiffRenderer> {
+    if use_colors(choice, output) {
+        Arc::new(colored_renderer())
+    } else {
+        Arc::new(brackets_renderer())
+    }
+}
+
+// Decide if colors must be displayed, with the environment variables in this order:
+//  - NO_COLOR set and not empty: no colors
+//  - CLICOLOR_FORCE set and not "0": colors are displayed even if the output is not a terminal
+//  - CLICOLOR set to "0": no colors
+fn colors_enabled(
+    choice: ColorChoice,
+    is_terminal: bool,
+    env: impl Fn(&str) -> Option<OsString>,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = env("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let forced = env("CLICOLOR_FORCE").is_some_and(|v| v != "0");
+            let disabled = env("CLICOLOR").is_some_and(|v| v == "0");
+            !no_color && (forced || (is_terminal && !disabled))
+        }
     }
-    .to_owned()
 }
 
 #[cfg(test)]
@@ -65,4 +228,103 @@ mod tests {
         assert_eq!("hello".to_string().color_as(Cyan), "\x1b[36mhello\x1b[0m");
         assert_eq!("hello".to_string().color_as(White), "\x1b[37mhello\x1b[0m");
     }
+
+    #[test]
+    fn test_extended_colors() {
+        assert_eq!(
+            "hello".to_string().color_as(BrightRed),
+            "\x1b[91mhello\x1b[0m"
+        );
+        assert_eq!(
+            "hello".to_string().color_as(Ansi256(208)),
+            "\x1b[38;5;208mhello\x1b[0m"
+        );
+        assert_eq!(
+            "hello".to_string().color_as(Rgb(255, 128, 0)),
+            "\x1b[38;2;255;128;0mhello\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_style() {
+        let style = Style {
+            background: Some(BrightBlue),
+            bold: true,
+            underline: true,
+            strikethrough: true,
+            inverse: true,
+            ..color_style(Red)
+        };
+        assert_eq!(
+            "hello".to_string().style_as(style),
+            "\x1b[1;4;7;9;31;104mhello\x1b[0m"
+        );
+        let style = Style {
+            background: Some(Rgb(1, 2, 3)),
+            ..plain_style()
+        };
+        assert_eq!('a'.style_as(style), "\x1b[48;2;1;2;3ma\x1b[0m");
+        // the plain style doesn't add any escape sequence
+        assert_eq!("hello".to_string().style_as(plain_style()), "hello");
+    }
+
+    #[test]
+    fn test_colors_enabled() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| OsString::from(v))
+            }
+        };
+        let auto = ColorChoice::Auto;
+        assert!(colors_enabled(
+            ColorChoice::Always,
+            false,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(!colors_enabled(
+            ColorChoice::Never,
+            true,
+            env(&[("CLICOLOR_FORCE", "1")])
+        ));
+        assert!(colors_enabled(auto, true, env(&[])));
+        assert!(!colors_enabled(auto, false, env(&[])));
+        assert!(!colors_enabled(auto, true, env(&[("NO_COLOR", "1")])));
+        // an empty NO_COLOR is ignored
+        assert!(colors_enabled(auto, true, env(&[("NO_COLOR", "")])));
+        assert!(!colors_enabled(auto, true, env(&[("CLICOLOR", "0")])));
+        assert!(colors_enabled(auto, true, env(&[("CLICOLOR", "1")])));
+        assert!(colors_enabled(auto, false, env(&[("CLICOLOR_FORCE", "1")])));
+        // NO_COLOR disables colors even when they are forced
+        assert!(!colors_enabled(
+            auto,
+            true,
+            env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")])
+        ));
+        assert!(colors_enabled(
+            auto,
+            true,
+            env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "")])
+        ));
+        assert!(!colors_enabled(
+            auto,
+            false,
+            env(&[("CLICOLOR_FORCE", "0")])
+        ));
+    }
+
+    #[test]
+    fn test_color_choice_renderer() {
+        let insert = crate::edit_operation::EditOperation::Insert("a".to_string());
+        assert_eq!(
+            color_choice_renderer(ColorChoice::Always, &std::io::stderr())
+                .render_operation(insert.clone()),
+            "\x1b[4;32ma\x1b[0m"
+        );
+        assert_eq!(
+            color_choice_renderer(ColorChoice::Never, &std::io::stderr()).render_operation(insert),
+            "+a"
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::iter::once;
+use unicode_normalization::UnicodeNormalization;
 use Cost::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
@@ -5,6 +7,7 @@ pub enum Cost {
     Insertion(usize),
     Deletion(usize),
     Substitution(usize),
+    Transposition(usize),
     NoAction(usize),
 }
 
@@ -16,6 +19,7 @@ impl Cost {
             Insertion(c) => c,
             Deletion(c) => c,
             Substitution(c) => c,
+            Transposition(c) => c,
             NoAction(c) => c,
         }
     }
@@ -26,6 +30,7 @@ pub fn show_cost(c: &Cost) -> String {
         Insertion(c) => format!("+{}", c),
         Deletion(c) => format!("-{}", c),
         Substitution(c) => format!("~{}", c),
+        Transposition(c) => format!("<>{}", c),
         NoAction(c) => format!("o{}", c),
     }
 }
@@ -36,15 +41,204 @@ pub trait Costs<T>: Copy {
     fn insertion_cost(self, t: &T) -> usize;
     fn deletion_cost(self, t: &T) -> usize;
     fn substitution_cost(self, t1: &T, t2: &T) -> usize;
+    // cost of swapping 2 adjacent elements. It is only used by the Damerau edit matrix
+    fn transposition_cost(self, _t1: &T, _t2: &T) -> usize {
+        1
+    }
     fn lower_cost(self, t1: &T, t2: &T, ins: usize, del: usize, sub: usize) -> Cost;
 }
 
 // Implementation of the Costs trait for the Levenshtein distance
+// It can be used for any kind of token which can be compared: chars, graphemes,...
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct LevenshteinCosts {}
 
-impl Costs<char> for LevenshteinCosts {
+impl<T: PartialEq> Costs<T> for LevenshteinCosts {
+    fn insertion_cost(self, _t: &T) -> usize {
+        1
+    }
+    fn deletion_cost(self, _t: &T) -> usize {
+        1
+    }
+    fn substitution_cost(self, t1: &T, t2: &T) -> usize {
+        if t1 == t2 {
+            0
+        } else {
+            1
+        }
+    }
+    fn lower_cost(self, t1: &T, t2: &T, ins: usize, del: usize, sub: usize) -> Cost {
+        levenshtein_lower_cost(t1 == t2, ins, del, sub)
+    }
+}
+
+pub fn levenshtein_costs() -> LevenshteinCosts {
+    LevenshteinCosts {}
+}
+
+// Select the lowest cost between an insertion, a deletion and a substitution
+// When costs are equal, the choice depends on the 2 tokens being the same or not
+fn levenshtein_lower_cost(same: bool, ins: usize, del: usize, sub: usize) -> Cost {
+    let (op_ins, op_del, op_sub) = (Insertion(ins), Deletion(del), Substitution(sub));
+    if ins < del {
+        if (ins < sub) || (ins == sub && same) {
+            op_ins
+        } else {
+            op_sub
+        }
+    } else if (del < sub) || (del == sub && same) {
+        op_del
+    } else {
+        op_sub
+    }
+}
+
+// Unicode normalization applied to tokens before comparing them
+//  - Canonical: NFC, "é" is the same as "e" followed by a combining acute accent
+//  - Compatibility: NFKC, additionally "ﬁ" is the same as "fi"
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Normalization {
+    Canonical,
+    Compatibility,
+}
+
+// Implementation of the Costs trait for the Levenshtein distance where some differences can be ignored
+//  - ignore_case: "A" is the same as "a" and "ß" is the same as "SS"
+//  - ignore_whitespace_amount: any run of whitespace is the same as a single space.
+//    When displaying differences, each run of whitespace is compared as a single token
+//  - normalization: tokens are normalized before being compared
+// Tokens which are considered as equal are kept instead of being substituted
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ComparisonCosts {
+    pub ignore_case: bool,
+    pub ignore_whitespace_amount: bool,
+    pub normalization: Option<Normalization>,
+}
+
+impl ComparisonCosts {
+    // Return the form of a token which is used for comparisons
+    pub fn normalize(self, t: &str) -> String {
+        let mut result: String = match self.normalization {
+            None => t.to_string(),
+            Some(Normalization::Canonical) => t.nfc().collect(),
+            Some(Normalization::Compatibility) => t.nfkc().collect(),
+        };
+        if self.ignore_case {
+            result = fold_case(&result);
+        }
+        if self.ignore_whitespace_amount {
+            result = collapse_whitespace(&result);
+        }
+        result
+    }
+
+    // Return true if 2 tokens are considered as equal.
+    // Tokens are only normalized when some differences are ignored
+    #[inline]
+    pub fn equivalent(self, t1: &str, t2: &str) -> bool {
+        t1 == t2 || (!self.ignores_nothing() && self.equivalent_normalized(t1, t2))
+    }
+
+    // Compare the normalized forms of 2 tokens.
+    // This is kept out of the comparisons done without normalization, which are much more frequent
+    #[inline(never)]
+    fn equivalent_normalized(self, t1: &str, t2: &str) -> bool {
+        self.normalize(t1) == self.normalize(t2)
+    }
+
+    // Return true if 2 chars are considered as equal, without allocating strings
+    #[inline]
+    pub fn equivalent_chars(self, c1: char, c2: char) -> bool {
+        c1 == c2 || (!self.ignores_nothing() && self.equivalent_normalized_chars(c1, c2))
+    }
+
+    // Compare the normalized forms of 2 chars
+    #[inline(never)]
+    fn equivalent_normalized_chars(self, c1: char, c2: char) -> bool {
+        match self.normalization {
+            None => self.equivalent_iterators(once(c1), once(c2)),
+            Some(Normalization::Canonical) => {
+                self.equivalent_iterators(once(c1).nfc(), once(c2).nfc())
+            }
+            Some(Normalization::Compatibility) => {
+                self.equivalent_iterators(once(c1).nfkc(), once(c2).nfkc())
+            }
+        }
+    }
+
+    // Return true if no difference is ignored
+    #[inline]
+    fn ignores_nothing(self) -> bool {
+        !self.ignore_case && !self.ignore_whitespace_amount && self.normalization.is_none()
+    }
+
+    // Compare 2 normalized sequences of chars, ignoring case and whitespace differences if necessary
+    fn equivalent_iterators(
+        self,
+        cs1: impl Iterator<Item = char>,
+        cs2: impl Iterator<Item = char>,
+    ) -> bool {
+        let whitespace = |c: char| {
+            if self.ignore_whitespace_amount && c.is_whitespace() {
+                ' '
+            } else {
+                c
+            }
+        };
+        if self.ignore_case {
+            cs1.flat_map(char::to_uppercase)
+                .flat_map(char::to_lowercase)
+                .map(whitespace)
+                .eq(cs2
+                    .flat_map(char::to_uppercase)
+                    .flat_map(char::to_lowercase)
+                    .map(whitespace))
+        } else {
+            cs1.map(whitespace).eq(cs2.map(whitespace))
+        }
+    }
+}
+
+// Return a form of a string where the case differences are removed.
+// The string is uppercased first so that "ß" is the same as "SS"
+fn fold_case(s: &str) -> String {
+    s.to_uppercase().to_lowercase()
+}
+
+// Replace each run of whitespace with a single space
+fn collapse_whitespace(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        if !c.is_whitespace() {
+            result.push(c);
+        } else if !result.ends_with(' ') {
+            result.push(' ');
+        }
+    }
+    result
+}
+
+impl Costs<String> for ComparisonCosts {
+    fn insertion_cost(self, _t: &String) -> usize {
+        1
+    }
+    fn deletion_cost(self, _t: &String) -> usize {
+        1
+    }
+    fn substitution_cost(self, t1: &String, t2: &String) -> usize {
+        if self.equivalent(t1, t2) {
+            0
+        } else {
+            1
+        }
+    }
+    fn lower_cost(self, t1: &String, t2: &String, ins: usize, del: usize, sub: usize) -> Cost {
+        levenshtein_lower_cost(self.equivalent(t1, t2), ins, del, sub)
+    }
+}
+
+impl Costs<char> for ComparisonCosts {
     fn insertion_cost(self, _t: &char) -> usize {
         1
     }
@@ -52,30 +246,64 @@ impl Costs<char> for LevenshteinCosts {
         1
     }
     fn substitution_cost(self, t1: &char, t2: &char) -> usize {
-        if t1 == t2 {
+        if self.equivalent_chars(*t1, *t2) {
             0
         } else {
             1
         }
     }
     fn lower_cost(self, t1: &char, t2: &char, ins: usize, del: usize, sub: usize) -> Cost {
-        let (op_ins, op_del, op_sub) = (Insertion(ins), Deletion(del), Substitution(sub));
-        if ins < del {
-            if (ins < sub) || (ins == sub && t1 == t2) {
-                op_ins
-            } else {
-                op_sub
-            }
-        } else if (del < sub) || (del == sub && t1 == t2) {
-            op_del
+        levenshtein_lower_cost(self.equivalent_chars(*t1, *t2), ins, del, sub)
+    }
+}
+
+// Comparison costs where no difference is ignored
+pub fn comparison_costs() -> ComparisonCosts {
+    ComparisonCosts {
+        ignore_case: false,
+        ignore_whitespace_amount: false,
+        normalization: None,
+    }
+}
+
+// Implementation of the Costs trait for lines of text
+// When several alignments have the same cost, inserting or deleting a line is preferred
+// over substituting it, so that the lines which are common to both texts are kept aligned.
+// Lines are compared with some comparison costs, so that ignored differences are ignored for whole lines
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LinesCosts {
+    pub comparison_costs: ComparisonCosts,
+}
+
+impl Costs<String> for LinesCosts {
+    fn insertion_cost(self, _t: &String) -> usize {
+        1
+    }
+    fn deletion_cost(self, _t: &String) -> usize {
+        1
+    }
+    fn substitution_cost(self, t1: &String, t2: &String) -> usize {
+        self.comparison_costs.substitution_cost(t1, t2)
+    }
+    fn lower_cost(self, t1: &String, t2: &String, ins: usize, del: usize, sub: usize) -> Cost {
+        if self.comparison_costs.equivalent(t1, t2) && sub <= ins && sub <= del {
+            Substitution(sub)
+        } else if ins <= del && ins <= sub {
+            Insertion(ins)
+        } else if del <= sub {
+            Deletion(del)
         } else {
-            op_sub
+            Substitution(sub)
         }
     }
 }
 
-pub fn levenshtein_costs() -> LevenshteinCosts {
-    LevenshteinCosts {}
+pub fn lines_costs() -> LinesCosts {
+    lines_costs_with(comparison_costs())
+}
+
+pub fn lines_costs_with(comparison_costs: ComparisonCosts) -> LinesCosts {
+    LinesCosts { comparison_costs }
 }
 
 #[cfg(test)]
@@ -87,6 +315,7 @@ mod tests {
         assert_eq!(show_cost(&Insertion(1)), "+1");
         assert_eq!(show_cost(&Deletion(1)), "-1");
         assert_eq!(show_cost(&Substitution(1)), "~1");
+        assert_eq!(show_cost(&Transposition(1)), "<>1");
         assert_eq!(show_cost(&NoAction(1)), "o1");
     }
     #[test]
@@ -104,4 +333,77 @@ mod tests {
         assert_eq!(lc.lower_cost(&'a', &'a', 2, 2, 2), Deletion(2));
         assert_eq!(lc.lower_cost(&'a', &'b', 2, 3, 2), Substitution(2));
     }
+    #[test]
+    fn test_comparison_cost() {
+        let s = |s: &str| s.to_string();
+        let mut cc = comparison_costs();
+        assert_eq!(cc.substitution_cost(&'a', &'A'), 1);
+        cc.ignore_case = true;
+        assert_eq!(cc.substitution_cost(&'a', &'A'), 0);
+        assert_eq!(cc.lower_cost(&'a', &'A', 1, 1, 1), Deletion(1));
+        assert_eq!(cc.lower_cost(&'a', &'b', 1, 1, 1), Substitution(1));
+        assert_eq!(cc.substitution_cost(&s("STRASSE"), &s("straße")), 0);
+        assert_eq!(cc.substitution_cost(&s("  "), &s(" ")), 1);
+        cc.ignore_whitespace_amount = true;
+        assert_eq!(cc.substitution_cost(&s("  "), &s(" ")), 0);
+        assert_eq!(cc.substitution_cost(&' ', &'\t'), 0);
+        assert_eq!(cc.substitution_cost(&s("a \t b"), &s("A b")), 0);
+        assert_eq!(cc.substitution_cost(&s("e\u{301}"), &s("é")), 1);
+        cc.normalization = Some(Normalization::Canonical);
+        assert_eq!(cc.substitution_cost(&s("e\u{301}"), &s("é")), 0);
+        assert_eq!(cc.substitution_cost(&s("É"), &s("e\u{301}")), 0);
+        // uppercasing "ﬁ" gives "FI", so ligatures are only compared without ignoring the case
+        cc.ignore_case = false;
+        assert_eq!(cc.substitution_cost(&s("ﬁ"), &s("fi")), 1);
+        cc.normalization = Some(Normalization::Compatibility);
+        assert_eq!(cc.substitution_cost(&s("ﬁ"), &s("fi")), 0);
+    }
+    #[test]
+    fn test_equivalent_chars() {
+        // chars are compared like strings made of a single char
+        let chars = [
+            'a', 'A', 'e', 'É', '\u{301}', ' ', '\t', 'ﬁ', 'Å', '\u{212B}', 'ß',
+        ];
+        let normalizations = [
+            None,
+            Some(Normalization::Canonical),
+            Some(Normalization::Compatibility),
+        ];
+        for ignore_case in [false, true] {
+            for ignore_whitespace_amount in [false, true] {
+                for normalization in normalizations {
+                    let cc = ComparisonCosts {
+                        ignore_case,
+                        ignore_whitespace_amount,
+                        normalization,
+                    };
+                    for c1 in chars {
+                        for c2 in chars {
+                            assert_eq!(
+                                cc.equivalent_chars(c1, c2),
+                                cc.equivalent(&c1.to_string(), &c2.to_string()),
+                                "{:?} {:?} {:?}",
+                                cc,
+                                c1,
+                                c2
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_lines_cost() {
+        let lc = lines_costs();
+        let (a, b) = ("a".to_string(), "b".to_string());
+        assert_eq!(lc.substitution_cost(&a, &a), 0);
+        assert_eq!(lc.substitution_cost(&a, &b), 1);
+        // keeping the same line is preferred
+        assert_eq!(lc.lower_cost(&a, &a, 1, 1, 1), Substitution(1));
+        // then inserting or deleting a line
+        assert_eq!(lc.lower_cost(&a, &b, 1, 2, 1), Insertion(1));
+        assert_eq!(lc.lower_cost(&a, &b, 2, 1, 1), Deletion(1));
+        assert_eq!(lc.lower_cost(&a, &b, 2, 2, 1), Substitution(1));
+    }
 }
@@ -1,8 +1,13 @@
-use crate::color::*;
+use crate::algorithm::*;
+use crate::costs::*;
 use crate::edit_operation::*;
+use crate::granularity::*;
+use crate::renderer::*;
 use crate::shorten::*;
+use crate::side_by_side::*;
 use crate::token::*;
-use Color::*;
+use std::mem::discriminant;
+use std::sync::Arc;
 use EditOperation::*;
 use Token::*;
 
@@ -32,82 +37,630 @@ pub fn make_char_separators(c1: char, c2: char) -> Separators {
 }
 
 // Options to use for displaying differences
-#[derive(PartialEq, Eq, Debug, Clone)]
+// Options are equal when they use the same renderer instance, since renderers can't be compared
+#[derive(Debug, Clone)]
 pub struct DisplayOptions {
-    pub separators: Separators,
+    pub renderer: Arc<dyn DiffRenderer>,
     pub shorten_options: ShortenOptions,
-    pub display_edit_operation: fn(EditOperation<char>) -> String,
+    pub line_options: LineOptions,
+    pub granularity: Granularity,
+    pub perspective: Perspective,
+    pub algorithm: Algorithm,
+    pub line_algorithm: Option<LineAlgorithm>,
+    pub comparison_costs: ComparisonCosts,
+}
+
+impl PartialEq for DisplayOptions {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.renderer, &other.renderer)
+            && self.shorten_options == other.shorten_options
+            && self.line_options == other.line_options
+            && self.granularity == other.granularity
+            && self.perspective == other.perspective
+            && self.algorithm == other.algorithm
+            && self.line_algorithm == other.line_algorithm
+            && self.comparison_costs == other.comparison_costs
+    }
+}
+
+impl Eq for DisplayOptions {}
+
+// Text from which the differences are displayed
+//  - First: the differences show how to go from the first text to the second one
+//  - Second: the differences show how to go from the second text to the first one
+//  - Both: both perspectives are displayed side by side, as "expected" vs "actual"
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Perspective {
+    First,
+    Second,
+    Both,
+}
+
+// Options to use for displaying lines
+//  - show_line_numbers prefixes each line with its number in the text used as the perspective
+//  - show_line_markers prefixes each line with a symbol showing if it has been kept, inserted, deleted or modified
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LineOptions {
+    pub show_line_numbers: bool,
+    pub show_line_markers: bool,
+}
+
+// Default line options: lines are displayed without any prefix
+pub fn default_line_options() -> LineOptions {
+    LineOptions {
+        show_line_numbers: false,
+        show_line_markers: false,
+    }
 }
 
 // Default display options
 pub fn default_display_options() -> DisplayOptions {
     DisplayOptions {
-        separators: brackets_separators(),
+        renderer: Arc::new(brackets_renderer()),
         shorten_options: ShortenOptions {
             size: 20,
             text: "...".to_string(),
         },
-        display_edit_operation: default_display_edit_operations,
+        line_options: default_line_options(),
+        granularity: default_granularity(),
+        perspective: Perspective::First,
+        algorithm: default_algorithm(),
+        line_algorithm: None,
+        comparison_costs: comparison_costs(),
     }
 }
 
-// Display an edit operation by prepending a symbol showing which operation is used
-pub fn default_display_edit_operations(e: EditOperation<char>) -> String {
+// Display the marker showing how a whole line has been edited
+pub fn line_marker(e: &EditOperation<String>) -> char {
     match e {
-        Insert(c) => format!("+{}", c),
-        Delete(c) => format!("-{}", c),
-        Substitute(c1, c2) => format!("~{}/{}", c1, c2),
-        Keep(c) => c.to_string(),
+        Insert(_) => '+',
+        Delete(_) => '-',
+        Substitute(_, _) | Transpose(_, _) => '~',
+        Keep(_) => ' ',
     }
 }
 
-// Display an edit operation using ascii colors: green = added, red = removed, blue = substituted
-pub fn colored_display_edit_operation(e: EditOperation<char>) -> String {
-    match e {
-        Insert(c) => c.color_as(Green),
-        Delete(c) => c.color_as(Red),
-        Substitute(c, _) => c.color_as(Cyan),
-        Keep(c) => c.to_string(),
-    }
-}
-// Show the differences by enclosing them in separators
-// Additionally shorten the text outside the separators if it is too long
-pub fn display_diffs(options: DisplayOptions, operations: Vec<EditOperation<char>>) -> String {
-    let start = options.separators.start_separator;
-    let end = options.separators.end_separator;
-    let mut result: Vec<Token> = vec![];
+// Show the differences with the renderer of the display options.
+// Additionally shorten the text outside the differences if it is too long.
+// Consecutive differences of the same kind are rendered as one run.
+// The text is only rendered after shortening, so that the renderer is only called
+// for the displayed text, in the order of that text
+pub fn display_diffs(options: DisplayOptions, operations: Vec<EditOperation<String>>) -> String {
+    let operations = if is_multi_chars(&options.granularity) {
+        merge_operations(operations)
+    } else {
+        operations
+    };
+    let renderer = options.renderer;
+    // kept operations and runs of differences of the same kind.
+    // They are referred to by their index in the tokens to shorten
+    let mut items: Vec<Vec<EditOperation<String>>> = vec![];
+    let mut tokens: Vec<Token> = vec![];
     let mut different = false;
 
     for operation in operations {
-        match operation {
-            Insert(_) | Delete(_) | Substitute(_, _) => {
-                if !different {
-                    result.push(Delimiter(start.clone()))
-                };
-                different = true;
-                result.push(Kept((options.display_edit_operation)(operation)))
+        let is_kept = matches!(operation, Keep(_));
+        if is_kept == different {
+            tokens.push(if different {
+                DifferenceEnd
+            } else {
+                DifferenceStart
+            });
+            different = !is_kept;
+        }
+        let index = items.len();
+        match items.last_mut() {
+            Some(run)
+                if !is_kept
+                    && run
+                        .last()
+                        .is_some_and(|last| discriminant(last) == discriminant(&operation)) =>
+            {
+                run.push(operation)
             }
-            Keep(_) => {
-                if different {
-                    result.push(Delimiter(end.clone()))
-                };
-                different = false;
-                result.push(Kept((options.display_edit_operation)(operation)))
+            _ => {
+                tokens.push(Item(index));
+                items.push(vec![operation]);
             }
         }
     }
     if different {
-        result.push(Delimiter(end.clone()));
+        tokens.push(DifferenceEnd);
     }
 
     let full_result: Vec<String> = shorten_tokens(
         options.shorten_options,
-        Delimiter(start),
-        Delimiter(end),
-        result,
+        DifferenceStart,
+        DifferenceEnd,
+        tokens,
     )
-    .iter()
-    .map(show_token)
+    .into_iter()
+    .map(|token| match token {
+        Item(index) => {
+            let item = items.get_mut(index).map(std::mem::take).unwrap_or_default();
+            render_item(renderer.as_ref(), item)
+        }
+        Elided(text) => renderer.render_elided(text),
+        DifferenceStart => renderer.start_difference(),
+        DifferenceEnd => renderer.end_difference(),
+        token => show_token(&token),
+    })
     .collect();
     full_result.join("")
 }
+
+// Render a kept operation or a run of differences
+fn render_item(renderer: &dyn DiffRenderer, mut item: Vec<EditOperation<String>>) -> String {
+    match item.as_slice() {
+        [Keep(_)] => item
+            .pop()
+            .map(|operation| renderer.render_operation(operation))
+            .unwrap_or_default(),
+        _ => renderer.render_run(item),
+    }
+}
+
+// Merge each sequence of consecutive differences into one deletion of all the removed tokens
+// followed by one insertion of all the added tokens: [-old words+new words]
+pub fn merge_operations(operations: Vec<EditOperation<String>>) -> Vec<EditOperation<String>> {
+    let mut result: Vec<EditOperation<String>> = vec![];
+    let mut deleted = String::new();
+    let mut inserted = String::new();
+
+    fn flush(result: &mut Vec<EditOperation<String>>, deleted: &mut String, inserted: &mut String) {
+        if !deleted.is_empty() {
+            result.push(Delete(std::mem::take(deleted)));
+        }
+        if !inserted.is_empty() {
+            result.push(Insert(std::mem::take(inserted)));
+        }
+    }
+
+    for operation in operations {
+        match operation {
+            Insert(t) => inserted.push_str(&t),
+            Delete(t) => deleted.push_str(&t),
+            Substitute(t1, t2) => {
+                deleted.push_str(&t1);
+                inserted.push_str(&t2);
+            }
+            Transpose(t1, t2) => {
+                deleted.push_str(&t1);
+                deleted.push_str(&t2);
+                inserted.push_str(&t2);
+                inserted.push_str(&t1);
+            }
+            Keep(t) => {
+                flush(&mut result, &mut deleted, &mut inserted);
+                result.push(Keep(t));
+            }
+        }
+    }
+    flush(&mut result, &mut deleted, &mut inserted);
+    result
+}
+
+// Show the differences line by line, keeping the original line breaks
+// Each line operation comes with the operations to apply on the tokens of that line
+// The differences are displayed from the perspective of the first text, the second text or both
+pub fn display_lines_diffs(
+    options: DisplayOptions,
+    line_operations: Vec<(EditOperation<String>, Vec<EditOperation<String>>)>,
+) -> String {
+    match options.perspective {
+        Perspective::First => display_lines(options, line_operations),
+        Perspective::Second => display_lines(options, inverse_lines_operations(line_operations)),
+        Perspective::Both => {
+            let expected = display_lines(options.clone(), line_operations.clone());
+            let actual = display_lines(options, inverse_lines_operations(line_operations));
+            display_expected_actual(expected, actual)
+        }
+    }
+}
+
+// Show the expected and actual texts side by side, line by line.
+// The columns are as wide as the widest line, so that the output doesn't depend on the terminal
+// and the lines are never wrapped
+fn display_expected_actual(expected: String, actual: String) -> String {
+    let mut rows = vec![("expected".to_string(), '|', "actual".to_string())];
+    for (e, a) in expected.split('\n').zip(actual.split('\n')) {
+        rows.push((e.to_string(), '|', a.to_string()));
+    }
+    let column = rows
+        .iter()
+        .map(|(e, _, a)| visible_width(e).max(visible_width(a)))
+        .max()
+        .unwrap_or(0);
+    display_columns(rows, column)
+}
+
+// Inverse the operations on lines and the operations on the tokens of each line
+fn inverse_lines_operations(
+    line_operations: Vec<(EditOperation<String>, Vec<EditOperation<String>>)>,
+) -> Vec<(EditOperation<String>, Vec<EditOperation<String>>)> {
+    line_operations
+        .into_iter()
+        .map(|(line_operation, operations)| {
+            (
+                inverse(line_operation),
+                operations.into_iter().map(inverse).collect(),
+            )
+        })
+        .collect()
+}
+
+// Show the differences line by line
+// Additionally each line can be prefixed with its number and a marker, depending on the line options
+fn display_lines(
+    options: DisplayOptions,
+    line_operations: Vec<(EditOperation<String>, Vec<EditOperation<String>>)>,
+) -> String {
+    let lines_nb = line_operations
+        .iter()
+        .filter(|(line_operation, _)| !matches!(line_operation, Insert(_)))
+        .count();
+    let width = lines_nb.to_string().len();
+    let mut line_number = 0;
+    let mut result: Vec<String> = vec![];
+
+    for (line_operation, operations) in line_operations {
+        let mut line = String::new();
+        if options.line_options.show_line_numbers {
+            // inserted lines don't have a number in the text used as the perspective
+            let number = match line_operation {
+                Insert(_) => String::new(),
+                _ => {
+                    line_number += 1;
+                    line_number.to_string()
+                }
+            };
+            line.push_str(&format!("{:>width$} ", number));
+        }
+        if options.line_options.show_line_markers {
+            line.push(line_marker(&line_operation));
+            line.push(' ');
+        }
+        // an empty line which is inserted or deleted doesn't have any token to display
+        // so it is displayed as an empty difference: [+] or [-]
+        let operations = match line_operation {
+            Insert(_) | Delete(_) if operations.is_empty() => vec![line_operation],
+            _ => operations,
+        };
+        line.push_str(&display_diffs(options.clone(), operations));
+        result.push(line);
+    }
+    result.join("\n")
+}
+
+// Options for displaying differences as a unified diff
+//  - old_name, new_name: names of the compared texts, displayed in the header
+//  - context_lines: number of unchanged lines displayed around each change
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UnifiedDiffOptions {
+    pub old_name: String,
+    pub new_name: String,
+    pub context_lines: usize,
+}
+
+// Default options, like `diff -u a b`
+pub fn default_unified_diff_options() -> UnifiedDiffOptions {
+    UnifiedDiffOptions {
+        old_name: "a".to_string(),
+        new_name: "b".to_string(),
+        context_lines: 3,
+    }
+}
+
+// Show the differences between 2 lists of lines as a unified diff:
+//
+// --- a
+// +++ b
+// @@ -1,3 +1,3 @@
+//  kept line
+// -deleted line
+// +inserted line
+//  kept line
+//
+// The lines must end with a new line, except for the last line of a text,
+// in which case it is marked with "\ No newline at end of file".
+// An empty string is returned when there are no differences
+pub fn display_unified_diff(
+    options: UnifiedDiffOptions,
+    line_operations: Vec<EditOperation<String>>,
+) -> String {
+    let lines = unified_lines(line_operations);
+    let changes: Vec<usize> = (0..lines.len()).filter(|i| lines[*i].0 != ' ').collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    let context = options.context_lines;
+    let mut result = format!("--- {}\n+++ {}\n", options.old_name, options.new_name);
+
+    // group the changes which are close enough to share their context lines
+    let mut index = 0;
+    while index < changes.len() {
+        let mut last = index;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * context + 1 {
+            last += 1;
+        }
+        let start = changes[index].saturating_sub(context);
+        let end = (changes[last] + context + 1).min(lines.len());
+        result.push_str(&display_hunk(&lines, start, end));
+        index = last + 1;
+    }
+    result
+}
+
+// Return the lines of a unified diff, prefixed with ' ', '-' or '+'.
+// In each run of changes the deleted lines are displayed before the inserted lines
+fn unified_lines(line_operations: Vec<EditOperation<String>>) -> Vec<(char, String)> {
+    let mut result: Vec<(char, String)> = vec![];
+    let mut inserted: Vec<(char, String)> = vec![];
+    for operation in line_operations {
+        match operation {
+            Keep(line) => {
+                result.append(&mut inserted);
+                result.push((' ', line));
+            }
+            Delete(line) => result.push(('-', line)),
+            Insert(line) => inserted.push(('+', line)),
+            Substitute(line1, line2) => {
+                result.push(('-', line1));
+                inserted.push(('+', line2));
+            }
+            Transpose(line1, line2) => {
+                result.push(('-', line1.clone()));
+                result.push(('-', line2.clone()));
+                inserted.push(('+', line2));
+                inserted.push(('+', line1));
+            }
+        }
+    }
+    result.append(&mut inserted);
+    result
+}
+
+// Display a hunk of lines with its header: @@ -old start,old size +new start,new size @@
+fn display_hunk(lines: &[(char, String)], start: usize, end: usize) -> String {
+    let old_start = lines[..start].iter().filter(|(c, _)| *c != '+').count();
+    let new_start = lines[..start].iter().filter(|(c, _)| *c != '-').count();
+    let old_size = lines[start..end].iter().filter(|(c, _)| *c != '+').count();
+    let new_size = lines[start..end].iter().filter(|(c, _)| *c != '-').count();
+    let mut result = format!(
+        "@@ -{} +{} @@\n",
+        hunk_range(old_start, old_size),
+        hunk_range(new_start, new_size)
+    );
+    for (prefix, line) in &lines[start..end] {
+        result.push(*prefix);
+        result.push_str(line);
+        if !line.ends_with('\n') {
+            result.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+    result
+}
+
+// Display the range of lines of a hunk. The lines are numbered from 1 and
+// an empty range is displayed with the number of the line before it
+fn hunk_range(start: usize, size: usize) -> String {
+    match size {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_options_equality() {
+        let options = default_display_options();
+        assert_eq!(options.clone(), options);
+        let mut other = options.clone();
+        other.perspective = Perspective::Second;
+        assert_ne!(other, options);
+        // renderers are compared by identity
+        let mut other = options.clone();
+        other.renderer = Arc::new(brackets_renderer());
+        assert_ne!(other, options);
+    }
+
+    fn line_operations() -> Vec<(EditOperation<String>, Vec<EditOperation<String>>)> {
+        let s = |s: &str| s.to_string();
+        vec![
+            (Keep(s("a")), vec![Keep(s("a"))]),
+            (Substitute(s("b"), s("c")), vec![Substitute(s("b"), s("c"))]),
+            (Insert(s("d")), vec![Insert(s("d"))]),
+            (Delete(s("e")), vec![Delete(s("e"))]),
+        ]
+    }
+
+    #[test]
+    fn test_merge_operations() {
+        let s = |s: &str| s.to_string();
+        assert_eq!(
+            merge_operations(vec![
+                Keep(s("the")),
+                Substitute(s("old"), s("new")),
+                Insert(s(" ")),
+                Delete(s("!")),
+                Keep(s("word"))
+            ]),
+            vec![
+                Keep(s("the")),
+                Delete(s("old!")),
+                Insert(s("new ")),
+                Keep(s("word"))
+            ]
+        );
+    }
+    #[test]
+    fn test_display_diffs_multi_chars() {
+        let s = |s: &str| s.to_string();
+        let mut options = default_display_options();
+        options.granularity = Granularity::Words;
+        let operations = vec![
+            Keep(s("the")),
+            Keep(s(" ")),
+            Substitute(s("old"), s("new")),
+            Keep(s(" ")),
+            Substitute(s("word"), s("text")),
+        ];
+        assert_eq!(
+            display_diffs(options, operations),
+            "the [-old+new] [-word+text]"
+        );
+    }
+    #[test]
+    fn test_display_lines_diffs() {
+        assert_eq!(
+            display_lines_diffs(default_display_options(), line_operations()),
+            "a\n[~b/c]\n[+d]\n[-e]"
+        );
+    }
+    #[test]
+    fn test_display_lines_diffs_with_numbers_and_markers() {
+        let mut options = default_display_options();
+        options.line_options = LineOptions {
+            show_line_numbers: true,
+            show_line_markers: true,
+        };
+        assert_eq!(
+            display_lines_diffs(options, line_operations()),
+            "1   a\n2 ~ [~b/c]\n  + [+d]\n3 - [-e]"
+        );
+    }
+    #[test]
+    fn test_display_lines_diffs_from_the_second_text() {
+        let mut options = default_display_options();
+        options.perspective = Perspective::Second;
+        options.line_options.show_line_numbers = true;
+        assert_eq!(
+            display_lines_diffs(options, line_operations()),
+            "1 a\n2 [~c/b]\n3 [-d]\n  [+e]"
+        );
+    }
+    #[test]
+    fn test_display_lines_diffs_from_both_texts() {
+        let mut options = default_display_options();
+        options.perspective = Perspective::Both;
+        assert_eq!(
+            display_lines_diffs(options, line_operations()),
+            [
+                "expected | actual",
+                "a        | a",
+                "[~b/c]   | [~c/b]",
+                "[+d]     | [-d]",
+                "[-e]     | [+e]"
+            ]
+            .join("\n")
+        );
+    }
+    #[test]
+    fn test_display_lines_diffs_from_both_texts_long_lines() {
+        let s = |s: &str| s.to_string();
+        let mut options = default_display_options();
+        options.perspective = Perspective::Both;
+        let long = "x".repeat(100);
+        // long lines are not wrapped
+        assert_eq!(
+            display_lines_diffs(
+                options,
+                vec![
+                    (Insert(long.clone()), vec![Insert(long.clone())]),
+                    (Keep(s("a")), vec![Keep(s("a"))])
+                ]
+            ),
+            format!(
+                "expected{} | actual\n[+{long}] | [-{long}]\na{} | a",
+                " ".repeat(95),
+                " ".repeat(102)
+            )
+        );
+    }
+    #[test]
+    fn test_display_unified_diff() {
+        let s = |s: &str| s.to_string();
+        let operations = vec![
+            Keep(s("a\n")),
+            Substitute(s("b\n"), s("x\n")),
+            Insert(s("y\n")),
+            Keep(s("c\n")),
+            Keep(s("d\n")),
+            Keep(s("e\n")),
+            Delete(s("f")),
+        ];
+        let mut options = default_unified_diff_options();
+        options.context_lines = 1;
+        // the 2 changes are too far apart to share their context
+        assert_eq!(
+            display_unified_diff(options.clone(), operations.clone()),
+            "--- a\n+++ b\n@@ -1,3 +1,4 @@\n a\n-b\n+x\n+y\n c\n@@ -5,2 +6 @@\n e\n-f\n\\ No newline at end of file\n"
+        );
+        options.context_lines = 2;
+        assert_eq!(
+            display_unified_diff(options, operations),
+            "--- a\n+++ b\n@@ -1,6 +1,6 @@\n a\n-b\n+x\n+y\n c\n d\n e\n-f\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_display_unified_diff_empty_ranges() {
+        let s = |s: &str| s.to_string();
+        let options = default_unified_diff_options();
+        assert_eq!(
+            display_unified_diff(options.clone(), vec![Keep(s("a\n"))]),
+            ""
+        );
+        assert_eq!(
+            display_unified_diff(options.clone(), vec![Insert(s("a\n"))]),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+a\n"
+        );
+        assert_eq!(
+            display_unified_diff(options, vec![Keep(s("a\n")), Delete(s("b\n"))]),
+            "--- a\n+++ b\n@@ -1,2 +1 @@\n a\n-b\n"
+        );
+    }
+
+    #[test]
+    fn test_display_diffs_colored_runs() {
+        let s = |s: &str| s.to_string();
+        let mut options = default_display_options();
+        options.renderer = Arc::new(colored_renderer());
+        let deleted: Vec<EditOperation<String>> = "abcdefghij"
+            .chars()
+            .map(|c| Delete(c.to_string()))
+            .collect();
+
+        // consecutive deletions are styled once
+        let displayed = display_diffs(options.clone(), deleted.clone());
+        assert_eq!(displayed, "[\x1b[9;31mabcdefghij\x1b[0m]");
+        let per_token: String = deleted
+            .into_iter()
+            .map(|operation| colored_renderer().render_operation(operation))
+            .collect();
+        // 23 bytes instead of 120 bytes when each deleted char is styled on its own
+        assert_eq!(displayed.len(), 23);
+        assert_eq!(per_token.len(), 120);
+
+        // a new run starts when the kind of operation changes
+        assert_eq!(
+            display_diffs(
+                options,
+                vec![
+                    Keep(s("x")),
+                    Delete(s("a")),
+                    Delete(s("b")),
+                    Insert(s("c")),
+                    Insert(s("d")),
+                    Keep(s("y")),
+                    Delete(s("e"))
+                ]
+            ),
+            "x[\x1b[9;31mab\x1b[0m\x1b[4;32mcd\x1b[0m]y[\x1b[9;31me\x1b[0m]"
+        );
+    }
+}
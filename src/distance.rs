@@ -0,0 +1,171 @@
+use crate::costs::*;
+use core::mem::*;
+
+// Return the Levenshtein distance between 2 lists of tokens
+pub fn distance<T: PartialEq>(ts1: &[T], ts2: &[T]) -> usize {
+    distance_with(&levenshtein_costs(), ts1, ts2)
+}
+
+// Return the distance between 2 lists of tokens for some given costs.
+// This gives the same result as the last cell of the edit matrix but only 2 rows
+// of costs are kept in memory, so it should be preferred when the edit operations are not needed
+pub fn distance_with<T: PartialEq>(costs: &impl Costs<T>, ts1: &[T], ts2: &[T]) -> usize {
+    let mut previous: Vec<usize> = vec![0; ts2.len() + 1];
+    let mut current: Vec<usize> = vec![0; ts2.len() + 1];
+    for (j, t2) in ts2.iter().enumerate() {
+        previous[j + 1] = previous[j] + costs.insertion_cost(t2);
+    }
+
+    for t1 in ts1.iter() {
+        current[0] = previous[0] + costs.deletion_cost(t1);
+        for (j, t2) in ts2.iter().enumerate() {
+            current[j + 1] = costs
+                .lower_cost(
+                    t1,
+                    t2,
+                    current[j] + costs.insertion_cost(t2), // insertion
+                    previous[j + 1] + costs.deletion_cost(t1), // deletion
+                    previous[j] + costs.substitution_cost(t1, t2), // substitution
+                )
+                .cost();
+        }
+        swap(&mut previous, &mut current);
+    }
+    previous[ts2.len()]
+}
+
+// Return the Levenshtein distance between 2 lists of tokens if it is not greater than k
+pub fn distance_within<T: PartialEq>(ts1: &[T], ts2: &[T], k: usize) -> Option<usize> {
+    distance_within_with(&levenshtein_costs(), ts1, ts2, k)
+}
+
+// Return the distance between 2 lists of tokens for some given costs if it is not greater than k.
+// Only the cells close enough to the diagonal of the edit matrix to have a cost lower than k are computed
+// (Ukkonen's cutoff) and the computation stops as soon as all the cells of a row are greater than k
+pub fn distance_within_with<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
+    k: usize,
+) -> Option<usize> {
+    // any cost greater than k is replaced with this value
+    let too_large = k.saturating_add(1);
+    let capped = |cost: usize| cost.min(too_large);
+
+    // each step away from the diagonal is an insertion or a deletion, which costs at least min_cost
+    let min_cost = ts1
+        .iter()
+        .map(|t| costs.deletion_cost(t))
+        .chain(ts2.iter().map(|t| costs.insertion_cost(t)))
+        .min()
+        .unwrap_or(0);
+    let band = k.checked_div(min_cost).unwrap_or(usize::MAX);
+    if ts1.len().abs_diff(ts2.len()) > band {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = vec![too_large; ts2.len() + 1];
+    let mut current: Vec<usize> = vec![too_large; ts2.len() + 1];
+    previous[0] = 0;
+    for (j, t2) in ts2.iter().enumerate().take(band) {
+        previous[j + 1] = capped(previous[j].saturating_add(costs.insertion_cost(t2)));
+    }
+
+    for (i, t1) in ts1.iter().enumerate() {
+        // columns of the band for the row i + 1
+        let first = (i + 1).saturating_sub(band);
+        let last = (i + 1).saturating_add(band).min(ts2.len());
+        if first == 0 {
+            current[0] = capped(previous[0].saturating_add(costs.deletion_cost(t1)));
+        } else {
+            current[first - 1] = too_large;
+        }
+        for j in first.max(1)..last + 1 {
+            let t2 = &ts2[j - 1];
+            let cost = costs
+                .lower_cost(
+                    t1,
+                    t2,
+                    current[j - 1].saturating_add(costs.insertion_cost(t2)), // insertion
+                    previous[j].saturating_add(costs.deletion_cost(t1)),     // deletion
+                    previous[j - 1].saturating_add(costs.substitution_cost(t1, t2)), // substitution
+                )
+                .cost();
+            current[j] = capped(cost);
+        }
+        if last < ts2.len() {
+            current[last + 1] = too_large;
+        }
+        if current[first..last + 1].iter().all(|c| *c > k) {
+            return None;
+        }
+        swap(&mut previous, &mut current);
+    }
+    Some(previous[ts2.len()]).filter(|d| *d <= k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_matrix::*;
+    use crate::keyboard::*;
+    use proptest::prelude::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(distance(&chars("kitten"), &chars("kitten")), 0);
+        assert_eq!(distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(distance(&chars("abc"), &chars("")), 3);
+        assert_eq!(distance(&chars("日本語"), &chars("日本")), 1);
+        assert_eq!(distance(&["a", "b"], &["b"]), 1);
+    }
+
+    #[test]
+    fn test_distance_within() {
+        assert_eq!(
+            distance_within(&chars("kitten"), &chars("sitting"), 3),
+            Some(3)
+        );
+        assert_eq!(
+            distance_within(&chars("kitten"), &chars("sitting"), 2),
+            None
+        );
+        assert_eq!(
+            distance_within(&chars("kitten"), &chars("kitten"), 0),
+            Some(0)
+        );
+        assert_eq!(distance_within(&chars(""), &chars(""), 0), Some(0));
+        assert_eq!(distance_within(&chars(""), &chars("abc"), 3), Some(3));
+        // the lengths are too different
+        assert_eq!(distance_within(&chars("a"), &chars("abcd"), 2), None);
+        // the first chars are already too different
+        assert_eq!(distance_within(&chars("xyz"), &chars("abc"), 1), None);
+        assert_eq!(
+            distance_within(&chars("abc"), &chars("abc"), usize::MAX),
+            Some(0)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_distance_is_the_last_matrix_cost(s1 in "\\PC{0,20}", s2 in "\\PC{0,20}") {
+            let matrix = create_edit_matrix(&levenshtein_costs(), &chars(&s1), &chars(&s2));
+            let last = matrix.get_value(matrix.rows_nb - 1, matrix.cols_nb - 1).map(|c| c.cost());
+            prop_assert_eq!(Some(distance(&chars(&s1), &chars(&s2))), last);
+        }
+        #[test]
+        fn prop_distance_within(s1 in "[a-d]{0,12}", s2 in "[a-d]{0,12}", k in 0..10usize) {
+            let (ts1, ts2) = (chars(&s1), chars(&s2));
+            let costs = keyboard_costs(KeyboardLayout::Qwerty);
+            let d = distance_with(&costs, &ts1, &ts2);
+            prop_assert_eq!(distance_within_with(&costs, &ts1, &ts2, k), Some(d).filter(|d| *d <= k));
+            let d = distance(&ts1, &ts2);
+            prop_assert_eq!(distance_within(&ts1, &ts2, k), Some(d).filter(|d| *d <= k));
+        }
+    }
+}
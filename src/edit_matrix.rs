@@ -2,37 +2,64 @@ use crate::costs::Cost::*;
 use crate::costs::*;
 use crate::matrix::*;
 
-pub fn create_edit_matrix(costs: &impl Costs<char>, str1: String, str2: String) -> Matrix<Cost> {
-    let mut matrix = init_matrix(str1.len() + 1, str2.len() + 1, NoAction(0));
-    let coordinates = cartesian(str1.len() + 1, str2.len() + 1);
-
-    for ij in coordinates {
-        let (i, j) = ij;
-        let new_cost: Cost = if i == 0 {
-            Insertion(j)
-        } else if j == 0 {
-            Deletion(i)
-        } else {
-            match cost_of(costs, str1.clone(), str2.clone(), i, j, &matrix) {
-                Some(c) => c,
-                _ => NoAction(0),
-            }
-        };
-        matrix.set_value(i, j, new_cost);
-    }
+// Create an edit matrix for 2 lists of tokens: characters, words, lines,...
+// Any type of token can be used as long as it can be compared for equality
+pub fn create_edit_matrix<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
+) -> Matrix<Cost> {
+    create_matrix(costs, ts1, ts2, false)
+}
 
-    matrix
+// Create an edit matrix where swapping 2 adjacent tokens is also a possible operation.
+// This computes the optimal string alignment distance, a restricted Damerau-Levenshtein distance
+// where a token can not be edited again once it has been transposed
+pub fn create_damerau_edit_matrix<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
+) -> Matrix<Cost> {
+    create_matrix(costs, ts1, ts2, true)
 }
 
-// Return the cartesian product of n x m elements, 0-indexed
-fn cartesian(n: usize, m: usize) -> Vec<(usize, usize)> {
-    let mut result = vec![];
-    for i in 0..n {
-        for j in 0..m {
-            result.push((i, j));
+fn create_matrix<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
+    with_transpositions: bool,
+) -> Matrix<Cost> {
+    let mut matrix = init_matrix(ts1.len() + 1, ts2.len() + 1, NoAction(0));
+
+    for i in 0..ts1.len() + 1 {
+        for j in 0..ts2.len() + 1 {
+            let new_cost: Cost = if i == 0 && j == 0 {
+                Insertion(0)
+            } else if i == 0 {
+                Insertion(cost_at(&matrix, i, j - 1) + costs.insertion_cost(&ts2[j - 1]))
+            } else if j == 0 {
+                Deletion(cost_at(&matrix, i - 1, j) + costs.deletion_cost(&ts1[i - 1]))
+            } else {
+                let cost = if with_transpositions {
+                    damerau_cost_of(costs, ts1, ts2, i, j, &matrix)
+                } else {
+                    cost_of(costs, ts1, ts2, i, j, &matrix)
+                };
+                match cost {
+                    Some(c) => c,
+                    _ => NoAction(0),
+                }
+            };
+            matrix.set_value(i, j, new_cost);
         }
     }
-    result
+
+    matrix
+}
+
+// Return the cost stored at coordinates i and j
+fn cost_at(matrix: &Matrix<Cost>, i: usize, j: usize) -> usize {
+    matrix.get_value(i, j).map(|c| c.cost()).unwrap_or(0)
 }
 
 // compute the cost of going from as1[i] to as2[j], knowing the existing costs
@@ -42,10 +69,10 @@ fn cartesian(n: usize, m: usize) -> Vec<(usize, usize)> {
 // going from (i-1, j) to (i, j) means that we delete as1[i]
 // going from (i-1, j-1) to (i, j) means that we substitute as1[i] with as2[j]
 // going from (i, j-1) to (i, j) means that we insert as2[j]
-pub fn cost_of(
-    costs: &impl Costs<char>,
-    str1: String,
-    str2: String,
+pub fn cost_of<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
     i: usize,
     j: usize,
     matrix: &Matrix<Cost>,
@@ -55,18 +82,18 @@ pub fn cost_of(
     let i1j = matrix.get_value(i1, j)?;
     let i1j1 = matrix.get_value(i1, j1)?;
     let ij1 = matrix.get_value(i, j1)?;
-    let v1 = str1.chars().nth(i1)?;
-    let v2 = str2.chars().nth(j1)?;
+    let v1 = ts1.get(i1)?;
+    let v2 = ts2.get(j1)?;
 
     let result = costs.lower_cost(
-        &v1,
-        &v2,
-        ij1.cost() + 1,                             // insertion
-        i1j.cost() + 1,                             // suppression
-        i1j1.cost() + if v1 == v2 { 0 } else { 1 }, // substitution
-    ); // insertion
-       // in case of a substitution if the resulting cost of (i, j) is the same as (i-1, j-1)
-       // this means that we have substituted the same letter and it is the same as doing no action
+        v1,
+        v2,
+        ij1.cost() + costs.insertion_cost(v2), // insertion
+        i1j.cost() + costs.deletion_cost(v1),  // suppression
+        i1j1.cost() + costs.substitution_cost(v1, v2), // substitution
+    );
+    // in case of a substitution if the resulting cost of (i, j) is the same as (i-1, j-1)
+    // this means that we have substituted the same letter and it is the same as doing no action
     match result {
         Substitution(_) => {
             if i1j1.cost() == result.cost() {
@@ -79,31 +106,174 @@ pub fn cost_of(
     }
 }
 
+// compute the cost of going from as1[i] to as2[j] when adjacent elements can also be swapped
+//  going from (i-2, j-2) to (i, j) means that we transpose as1[i-1] and as1[i]
+//  when as1[i-1] is equivalent to as2[j] and as1[i] is equivalent to as2[j-1] for the costs
+pub fn damerau_cost_of<T: PartialEq>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    ts2: &[T],
+    i: usize,
+    j: usize,
+    matrix: &Matrix<Cost>,
+) -> Option<Cost> {
+    let result = cost_of(costs, ts1, ts2, i, j, matrix)?;
+    if i > 1 && j > 1 {
+        let (a1, b1) = (ts1.get(i - 2)?, ts1.get(i - 1)?);
+        let (a2, b2) = (ts2.get(j - 2)?, ts2.get(j - 1)?);
+        let equivalent = |t1: &T, t2: &T| costs.substitution_cost(t1, t2) == 0;
+        if equivalent(a1, b2) && equivalent(b1, a2) && !equivalent(a1, b1) {
+            let transposition =
+                matrix.get_value(i - 2, j - 2)?.cost() + costs.transposition_cost(a1, b1);
+            if transposition < result.cost() {
+                return Some(Transposition(transposition));
+            }
+        }
+    }
+    Some(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::distance::*;
+    use crate::edit_operation::EditOperation::*;
+    use crate::edit_operation::*;
+    use proptest::prelude::*;
+
+    // Costs where whitespace edits are cheap and substituting digits is expensive
+    #[derive(Clone, Copy)]
+    struct WeightedCosts {}
+
+    impl Costs<char> for WeightedCosts {
+        fn insertion_cost(self, t: &char) -> usize {
+            if t.is_whitespace() {
+                0
+            } else {
+                2
+            }
+        }
+        fn deletion_cost(self, t: &char) -> usize {
+            self.insertion_cost(t)
+        }
+        fn substitution_cost(self, t1: &char, t2: &char) -> usize {
+            if t1 == t2 {
+                0
+            } else if t1.is_ascii_digit() || t2.is_ascii_digit() {
+                10
+            } else {
+                1
+            }
+        }
+        fn lower_cost(self, t1: &char, t2: &char, ins: usize, del: usize, sub: usize) -> Cost {
+            levenshtein_costs().lower_cost(t1, t2, ins, del, sub)
+        }
+    }
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
 
     #[test]
-    fn test_cartesian() {
+    fn test_create_edit_matrix_for_lines() {
+        let lines1 = vec!["a".to_string(), "b".to_string()];
+        let lines2 = vec!["b".to_string()];
         assert_eq!(
-            cartesian(2, 3),
-            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+            create_edit_matrix(&lines_costs(), &lines1, &lines2),
+            matrix_from_rows(vec![
+                vec![Insertion(0), Insertion(1)],
+                vec![Deletion(1), Substitution(1)],
+                vec![Deletion(2), NoAction(1)]
+            ])
         );
     }
     #[test]
     fn test_create_edit_matrix() {
         assert_eq!(
-            create_edit_matrix(&levenshtein_costs(), "hello".to_string(), "hey".to_string()),
-            Matrix {
-                rows: vec![
-                    vec![Insertion(0), Insertion(1), Insertion(2), Insertion(3)],
-                    vec![Deletion(1), NoAction(0), Insertion(1), Insertion(2)],
-                    vec![Deletion(2), Deletion(1), NoAction(0), Insertion(1)],
-                    vec![Deletion(3), Deletion(2), Deletion(1), Substitution(1)],
-                    vec![Deletion(4), Deletion(3), Deletion(2), Substitution(2)],
-                    vec![Deletion(5), Deletion(4), Deletion(3), Substitution(3)]
-                ]
-            }
+            create_edit_matrix(&levenshtein_costs(), &chars("hello"), &chars("hey")),
+            matrix_from_rows(vec![
+                vec![Insertion(0), Insertion(1), Insertion(2), Insertion(3)],
+                vec![Deletion(1), NoAction(0), Insertion(1), Insertion(2)],
+                vec![Deletion(2), Deletion(1), NoAction(0), Insertion(1)],
+                vec![Deletion(3), Deletion(2), Deletion(1), Substitution(1)],
+                vec![Deletion(4), Deletion(3), Deletion(2), Substitution(2)],
+                vec![Deletion(5), Deletion(4), Deletion(3), Substitution(3)]
+            ])
+        );
+    }
+    #[test]
+    fn test_create_edit_matrix_unicode() {
+        let matrix = create_edit_matrix(&levenshtein_costs(), &chars("né"), &chars("ne"));
+        assert_eq!(matrix.rows_nb, 3);
+        assert_eq!(matrix.get_value(2, 2), Some(&Substitution(1)));
+    }
+
+    #[test]
+    fn test_create_edit_matrix_with_weighted_costs() {
+        let costs = WeightedCosts {};
+        assert_eq!(
+            create_edit_matrix(&costs, &chars("a1"), &chars(" b2")),
+            matrix_from_rows(vec![
+                vec![Insertion(0), Insertion(0), Insertion(2), Insertion(4)],
+                vec![Deletion(2), Substitution(1), Substitution(1), Insertion(3)],
+                vec![Deletion(4), Deletion(3), Deletion(3), Deletion(5)]
+            ])
+        );
+    }
+    #[test]
+    fn test_edit_operations_with_weighted_costs() {
+        let costs = WeightedCosts {};
+        let (s1, s2) = (chars("a1"), chars(" b2"));
+        assert_eq!(
+            edit_operations(&s1, &s2, &costs),
+            vec![Insert(' '), Substitute('a', 'b'), Insert('2'), Delete('1')]
         );
+        assert_eq!(distance_with(&costs, &s1, &s2), 5);
+    }
+
+    #[test]
+    fn test_create_damerau_edit_matrix() {
+        assert_eq!(
+            create_damerau_edit_matrix(&levenshtein_costs(), &chars("teh"), &chars("the")),
+            matrix_from_rows(vec![
+                vec![Insertion(0), Insertion(1), Insertion(2), Insertion(3)],
+                vec![Deletion(1), NoAction(0), Insertion(1), Insertion(2)],
+                vec![Deletion(2), Deletion(1), Substitution(1), NoAction(1)],
+                vec![Deletion(3), Deletion(2), NoAction(1), Transposition(1)]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_damerau_transposition_uses_costs() {
+        // the swapped elements are compared with the costs
+        let mut costs = comparison_costs();
+        costs.ignore_case = true;
+        let matrix = create_damerau_edit_matrix(&costs, &chars("Ab"), &chars("bA"));
+        assert_eq!(matrix.get_value(2, 2), Some(&Transposition(1)));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_create_edit_matrix_size(s1 in "\\PC{0,10}", s2 in "\\PC{0,10}") {
+            let matrix = create_edit_matrix(&levenshtein_costs(), &chars(&s1), &chars(&s2));
+            prop_assert_eq!(matrix.rows_nb, s1.chars().count() + 1);
+            prop_assert_eq!(matrix.cols_nb, s2.chars().count() + 1);
+        }
+        #[test]
+        fn prop_distance_with_weighted_costs(s1 in "[a-c1-3 ]{0,10}", s2 in "[a-c1-3 ]{0,10}") {
+            let costs = WeightedCosts {};
+            let matrix = create_edit_matrix(&costs, &chars(&s1), &chars(&s2));
+            let last = matrix.get_value(matrix.rows_nb - 1, matrix.cols_nb - 1).map(|c| c.cost());
+            prop_assert_eq!(Some(distance_with(&costs, &chars(&s1), &chars(&s2))), last);
+        }
+        #[test]
+        fn prop_damerau_distance_is_lower(s1 in "[a-d]{0,10}", s2 in "[a-d]{0,10}") {
+            let costs = levenshtein_costs();
+            let last = |m: Matrix<Cost>| m.get_value(m.rows_nb - 1, m.cols_nb - 1).map(|c| c.cost());
+            let levenshtein = last(create_edit_matrix(&costs, &chars(&s1), &chars(&s2)));
+            let damerau = last(create_damerau_edit_matrix(&costs, &chars(&s1), &chars(&s2)));
+            prop_assert!(damerau <= levenshtein);
+        }
     }
 }
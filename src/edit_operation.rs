@@ -1,5 +1,7 @@
 use crate::costs::*;
+use crate::edit_matrix::*;
 use crate::matrix::*;
+use std::fmt;
 use Cost::*;
 use EditOperation::*;
 
@@ -10,6 +12,7 @@ pub enum EditOperation<T> {
     Insert(T),
     Delete(T),
     Substitute(T, T),
+    Transpose(T, T),
     Keep(T),
 }
 
@@ -20,79 +23,310 @@ pub fn inverse<T>(e: EditOperation<T>) -> EditOperation<T> {
         Insert(t) => Delete(t),
         Delete(t) => Insert(t),
         Substitute(t1, t2) => Substitute(t2, t1),
+        Transpose(t1, t2) => Transpose(t2, t1),
         Keep(t) => Keep(t),
     }
 }
 
+// Error returned when a list of edit operations can not be applied to a list of tokens.
+// The position is the index of the token in the list of tokens
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ApplyError {
+    // a kept, deleted or substituted token is not the token found at that position
+    Mismatch { position: usize },
+    // the operations expect a token after the end of the list
+    MissingToken { position: usize },
+    // the tokens starting at this position are not processed by the operations
+    RemainingTokens { position: usize },
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::Mismatch { position } => {
+                write!(
+                    f,
+                    "the token at position {} does not match the edit operation",
+                    position
+                )
+            }
+            ApplyError::MissingToken { position } => {
+                write!(
+                    f,
+                    "a token is expected at position {} but the input is too short",
+                    position
+                )
+            }
+            ApplyError::RemainingTokens { position } => {
+                write!(
+                    f,
+                    "the tokens starting at position {} are not edited",
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+// Apply a list of edit operations to a list of tokens and return the edited list of tokens.
+// Kept, deleted and substituted tokens must be the ones found in the input
+pub fn apply<T: PartialEq + Clone>(
+    operations: &[EditOperation<T>],
+    ts: &[T],
+) -> Result<Vec<T>, ApplyError> {
+    let mut result: Vec<T> = vec![];
+    let mut position = 0;
+
+    for operation in operations {
+        match operation {
+            Insert(t) => result.push(t.clone()),
+            Delete(t) => consume(ts, t, &mut position)?,
+            Substitute(t1, t2) => {
+                consume(ts, t1, &mut position)?;
+                result.push(t2.clone());
+            }
+            Transpose(t1, t2) => {
+                consume(ts, t1, &mut position)?;
+                consume(ts, t2, &mut position)?;
+                result.push(t2.clone());
+                result.push(t1.clone());
+            }
+            Keep(t) => {
+                consume(ts, t, &mut position)?;
+                result.push(t.clone());
+            }
+        }
+    }
+    if position < ts.len() {
+        Err(ApplyError::RemainingTokens { position })
+    } else {
+        Ok(result)
+    }
+}
+
+// Check that the token at the current position is the expected one and move to the next position
+fn consume<T: PartialEq>(ts: &[T], expected: &T, position: &mut usize) -> Result<(), ApplyError> {
+    match ts.get(*position) {
+        None => Err(ApplyError::MissingToken {
+            position: *position,
+        }),
+        Some(t) if t != expected => Err(ApplyError::Mismatch {
+            position: *position,
+        }),
+        Some(_) => {
+            *position += 1;
+            Ok(())
+        }
+    }
+}
+
+// Return the list of operations necessary to go from one list of tokens to another
+// for some given costs
+pub fn edit_operations<T: PartialEq + Clone>(
+    ts1: &[T],
+    ts2: &[T],
+    costs: &impl Costs<T>,
+) -> Vec<EditOperation<T>> {
+    let matrix = create_edit_matrix(costs, ts1, ts2);
+    make_edit_operations(ts1, ts2, &matrix)
+}
+
+// Return the list of operations necessary to go from one list of tokens to another
+// for some given costs, when adjacent tokens can also be transposed
+pub fn damerau_edit_operations<T: PartialEq + Clone>(
+    ts1: &[T],
+    ts2: &[T],
+    costs: &impl Costs<T>,
+) -> Vec<EditOperation<T>> {
+    let matrix = create_damerau_edit_matrix(costs, ts1, ts2);
+    make_edit_operations(ts1, ts2, &matrix)
+}
+
 // From the original lists of characters, given the cost matrix
 // return a list of edit operations allowing to edit one text and eventually get the second one
 pub fn make_edit_operations<T: Clone>(
-    ts1: Vec<T>,
-    ts2: Vec<T>,
-    matrix: Matrix<Cost>,
+    ts1: &[T],
+    ts2: &[T],
+    matrix: &Matrix<Cost>,
 ) -> Vec<EditOperation<T>> {
     let mut result: Vec<EditOperation<T>> = vec![];
-    if ts1.is_empty() || ts2.is_empty() {
-        return result;
-    }
 
-    let ts1_size = ts1.len();
-    let ts2_size = ts2.len();
-
-    fn go<T: Clone>(
-        matrix: Matrix<Cost>,
-        ts1: Vec<T>,
-        ts2: Vec<T>,
-        i1: usize,
-        j1: usize,
-        result: &mut Vec<EditOperation<T>>,
-    ) {
-        let (i, j) = (i1 - 1, j1 - 1);
-        if i == 0 && j == 0 {
-            return;
-        }
-        if let Some(op) = matrix.get_value(i, j) {
-            let dist = op.cost();
-            if i == 1 && j == 1 {
-                if dist == 0 {
-                    if let Some(t) = ts1.first() {
-                        result.push(Keep((*t).clone()));
-                    }
-                } else if let (Some(t1), Some(t2)) = (ts1.first(), ts2.first()) {
-                    result.push(Substitute((*t1).clone(), (*t2).clone()));
-                }
-            } else if j1 == 0 {
-                result.extend(ts1.iter().take(i).map(|t| Delete((*t).clone())));
-            } else if i1 == 0 {
-                result.extend(ts2.iter().take(j).map(|t| Delete((*t).clone())));
-            } else {
-                match op {
-                    Insertion(_) => {
-                        result.push(Insert(ts2[j - 1].clone()));
-                        go(matrix, ts1, ts2, i1, j1 - 1, result);
-                    }
-                    Deletion(_) => {
-                        result.push(Delete(ts1[i - 1].clone()));
-                        go(matrix, ts1, ts2, i1 - 1, j1, result);
-                    }
-                    Substitution(_) => {
-                        result.push(Substitute(ts1[i - 1].clone(), ts2[j - 1].clone()));
-                        go(matrix, ts1, ts2, i1 - 1, j1 - 1, result);
-                    }
-                    _ => {
-                        result.push(Keep(ts1[i - 1].clone()));
-                        go(matrix, ts1, ts2, i1 - 1, j1 - 1, result);
-                    }
-                }
+    // We go back from the last cell of the matrix to the first one by following the operations
+    // which were selected for each cell. i and j are the number of tokens from ts1 and ts2
+    // which still need to be processed, so ts1[i - 1] and ts2[j - 1] are the current tokens
+    let (mut i, mut j) = (ts1.len(), ts2.len());
+    while i > 0 || j > 0 {
+        match matrix.get_value(i, j) {
+            Some(Insertion(_)) if j > 0 => {
+                result.push(Insert(ts2[j - 1].clone()));
+                j -= 1;
+            }
+            Some(Deletion(_)) if i > 0 => {
+                result.push(Delete(ts1[i - 1].clone()));
+                i -= 1;
+            }
+            Some(Substitution(_)) if i > 0 && j > 0 => {
+                result.push(Substitute(ts1[i - 1].clone(), ts2[j - 1].clone()));
+                i -= 1;
+                j -= 1;
             }
+            Some(Transposition(_)) if i > 1 && j > 1 => {
+                result.push(Transpose(ts1[i - 2].clone(), ts1[i - 1].clone()));
+                i -= 2;
+                j -= 2;
+            }
+            Some(NoAction(_)) if i > 0 && j > 0 => {
+                result.push(Keep(ts1[i - 1].clone()));
+                i -= 1;
+                j -= 1;
+            }
+            _ => break,
         }
     }
-
-    // this is a bit tricky. We iterate with i1 = i + 1, j1 = j + 1
-    // where i and j are the real indices used to fetch values in the matrix and
-    // in the strings but i1 and j1 are used to make comparison against 0
-    // whereas checking if i and j are negative would not compile
-    go(matrix, ts1, ts2, ts1_size + 1, ts2_size + 1, &mut result);
     result.reverse();
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // A custom type of token
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    enum Node {
+        Number(u32),
+        Plus,
+        Times,
+    }
+    use Node::*;
+
+    #[test]
+    fn test_edit_operations_on_words() {
+        let words1 = vec!["the", "quick", "fox"];
+        let words2 = vec!["the", "slow", "fox", "jumps"];
+        assert_eq!(
+            edit_operations(&words1, &words2, &levenshtein_costs()),
+            vec![
+                Keep("the"),
+                Substitute("quick", "slow"),
+                Keep("fox"),
+                Insert("jumps")
+            ]
+        );
+    }
+    #[test]
+    fn test_edit_operations_on_custom_tokens() {
+        let nodes1 = vec![Number(1), Plus, Number(2)];
+        let nodes2 = vec![Number(1), Times, Number(2)];
+        assert_eq!(
+            edit_operations(&nodes1, &nodes2, &levenshtein_costs()),
+            vec![Keep(Number(1)), Substitute(Plus, Times), Keep(Number(2))]
+        );
+    }
+    #[test]
+    fn test_make_edit_operations_on_large_inputs() {
+        // the operations are computed without recursion so there is no stack overflow
+        let n = 300_000;
+        let mut ts1 = vec![0; n];
+        ts1[n / 2] = 1;
+        let ts2 = vec![1];
+        let operations = edit_operations(&ts1, &ts2, &levenshtein_costs());
+        assert_eq!(operations.len(), n);
+        assert_eq!(operations[n / 2], Keep(1));
+        assert_eq!(
+            operations.iter().filter(|o| **o == Delete(0)).count(),
+            n - 1
+        );
+    }
+    #[test]
+    fn test_edit_operations_on_empty_inputs() {
+        let empty: Vec<char> = vec![];
+        let abc = vec!['a', 'b', 'c'];
+        let costs = levenshtein_costs();
+        assert_eq!(edit_operations(&empty, &empty, &costs), vec![]);
+        assert_eq!(
+            edit_operations(&empty, &abc, &costs),
+            vec![Insert('a'), Insert('b'), Insert('c')]
+        );
+        assert_eq!(
+            edit_operations(&abc, &empty, &costs),
+            vec![Delete('a'), Delete('b'), Delete('c')]
+        );
+        assert_eq!(
+            edit_operations(&['a'], &['b', 'a'], &costs),
+            vec![Insert('b'), Keep('a')]
+        );
+        assert_eq!(
+            edit_operations(&['b', 'a'], &['a'], &costs),
+            vec![Delete('b'), Keep('a')]
+        );
+        assert_eq!(
+            edit_operations(&['a'], &['b'], &costs),
+            vec![Substitute('a', 'b')]
+        );
+    }
+
+    #[test]
+    fn test_damerau_edit_operations() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        let costs = levenshtein_costs();
+        assert_eq!(
+            damerau_edit_operations(&chars("teh"), &chars("the"), &costs),
+            vec![Keep('t'), Transpose('e', 'h')]
+        );
+        assert_eq!(
+            damerau_edit_operations(&chars("abcd"), &chars("bacd"), &costs),
+            vec![Transpose('a', 'b'), Keep('c'), Keep('d')]
+        );
+        assert_eq!(
+            edit_operations(&chars("teh"), &chars("the"), &costs),
+            vec![Keep('t'), Substitute('e', 'h'), Substitute('h', 'e')]
+        );
+    }
+    #[test]
+    fn test_apply() {
+        let operations = vec![Keep('a'), Substitute('b', 'x'), Delete('c'), Insert('d')];
+        assert_eq!(
+            apply(&operations, &['a', 'b', 'c']),
+            Ok(vec!['a', 'x', 'd'])
+        );
+        assert_eq!(
+            apply(&[], &['a']),
+            Err(ApplyError::RemainingTokens { position: 0 })
+        );
+        assert_eq!(
+            apply(&operations, &['a', 'b', 'e']),
+            Err(ApplyError::Mismatch { position: 2 })
+        );
+        assert_eq!(
+            apply(&operations, &['a', 'b']),
+            Err(ApplyError::MissingToken { position: 2 })
+        );
+        assert_eq!(
+            apply(&operations, &['a', 'b', 'c', 'd']),
+            Err(ApplyError::RemainingTokens { position: 3 })
+        );
+        assert_eq!(
+            ApplyError::Mismatch { position: 2 }.to_string(),
+            "the token at position 2 does not match the edit operation"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_edit_operations_transform_the_first_input(s1 in "[a-d]{0,12}", s2 in "[a-d]{0,12}") {
+            let (ts1, ts2): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+            let operations = edit_operations(&ts1, &ts2, &levenshtein_costs());
+            prop_assert_eq!(apply(&operations, &ts1), Ok(ts2));
+        }
+        #[test]
+        fn prop_damerau_edit_operations_transform_the_first_input(s1 in "[a-d]{0,12}", s2 in "[a-d]{0,12}") {
+            let (ts1, ts2): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+            let operations = damerau_edit_operations(&ts1, &ts2, &levenshtein_costs());
+            prop_assert_eq!(apply(&operations, &ts1), Ok(ts2));
+        }
+    }
+}
@@ -1,20 +1,54 @@
+use crate::algorithm::*;
+pub use crate::color::ColorChoice;
+use crate::color::*;
 use crate::costs::*;
 pub use crate::difference::*;
-use crate::edit_matrix::*;
 use crate::edit_operation::*;
+use crate::granularity::*;
+use crate::myers::*;
+pub use crate::renderer::*;
+pub use crate::side_by_side::*;
+use EditOperation::*;
 
 // Show the distance between 2 pieces of text
 pub fn show_distance<S1: Into<String>, S2: Into<String>>(s1: S1, s2: S2) -> String {
     show_distance_with(default_split_size(), default_display_options(), s1, s2)
 }
 
-// Show the distance between 2 pieces of text with colors instead of symbols
+// Show the distance between 2 pieces of text with colors instead of symbols.
+// Colors are only used when stderr, where assertion messages are written, is a terminal
+// and they are not disabled by the environment
 pub fn show_distance_colored<S1: Into<String>, S2: Into<String>>(s1: S1, s2: S2) -> String {
+    show_distance_colored_with(ColorChoice::Auto, s1, s2)
+}
+
+// Show the distance between 2 pieces of text with colors instead of symbols, depending on a color choice.
+// With the Auto choice, colors are used if stderr is a terminal.
+// Use color_choice_renderer in the display options to check another output
+pub fn show_distance_colored_with<S1: Into<String>, S2: Into<String>>(
+    color_choice: ColorChoice,
+    s1: S1,
+    s2: S2,
+) -> String {
     let mut options = default_display_options();
-    options.display_edit_operation = colored_display_edit_operation;
+    options.renderer = color_choice_renderer(color_choice, &std::io::stderr());
     show_distance_with(default_split_size(), options, s1, s2)
 }
 
+// Show the distance between 2 pieces of text from the perspective of the second text
+pub fn show_distance_inverse<S1: Into<String>, S2: Into<String>>(s1: S1, s2: S2) -> String {
+    let mut options = default_display_options();
+    options.perspective = Perspective::Second;
+    show_distance_with(default_split_size(), options, s1, s2)
+}
+
+// Show the distance between 2 pieces of text from the perspective of both texts: expected vs actual
+pub fn show_distance_both<S1: Into<String>, S2: Into<String>>(expected: S1, actual: S2) -> String {
+    let mut options = default_display_options();
+    options.perspective = Perspective::Both;
+    show_distance_with(default_split_size(), options, expected, actual)
+}
+
 // Show the distance between 2 pieces of text and specify splitting / display options
 pub fn show_distance_with<S1: Into<String>, S2: Into<String>>(
     split_size: SplitSize,
@@ -22,15 +56,222 @@ pub fn show_distance_with<S1: Into<String>, S2: Into<String>>(
     s1: S1,
     s2: S2,
 ) -> String {
-    let mut result: Vec<String> = vec![];
-    let splitted1 = split(split_size.clone(), s1.into());
-    let splitted2 = split(split_size, s2.into());
-    let zipped = splitted1.iter().zip(splitted2.iter());
-    for (line1, line2) in zipped {
-        let operations = levenshtein_operations(line1.clone(), line2.clone());
-        result.push(display_diffs(display_options.clone(), operations));
+    let operations =
+        lines_and_tokens_operations(split_size, &display_options, s1.into(), s2.into());
+    display_lines_diffs(display_options, operations)
+}
+
+// Show the differences between 2 pieces of text in 2 columns
+pub fn show_side_by_side<S1: Into<String>, S2: Into<String>>(s1: S1, s2: S2) -> String {
+    show_side_by_side_with(
+        default_split_size(),
+        default_display_options(),
+        default_side_by_side_options(),
+        s1,
+        s2,
+    )
+}
+
+// Show the differences between 2 pieces of text in 2 columns and specify splitting / display options
+pub fn show_side_by_side_with<S1: Into<String>, S2: Into<String>>(
+    split_size: SplitSize,
+    display_options: DisplayOptions,
+    side_by_side_options: SideBySideOptions,
+    s1: S1,
+    s2: S2,
+) -> String {
+    let operations =
+        lines_and_tokens_operations(split_size, &display_options, s1.into(), s2.into());
+    display_side_by_side(display_options, side_by_side_options, operations)
+}
+
+// Return the operations on the lines of 2 pieces of text
+// Each line operation comes with the operations on the tokens of that line
+fn lines_and_tokens_operations(
+    split_size: SplitSize,
+    display_options: &DisplayOptions,
+    s1: String,
+    s2: String,
+) -> Vec<(EditOperation<String>, Vec<EditOperation<String>>)> {
+    let mut result: Vec<(EditOperation<String>, Vec<EditOperation<String>>)> = vec![];
+    let granularity = &display_options.granularity;
+    let lines1 = lines(s1);
+    let lines2 = lines(s2);
+    let costs = display_options.comparison_costs;
+    let algorithm = display_options.algorithm;
+    let line_algorithm = display_options.line_algorithm;
+    let aligned = lines_operations(algorithm, line_algorithm, costs, lines1, lines2);
+    for line_operation in aligned {
+        let operations = match line_operation.clone() {
+            Keep(line) => line_tokens(granularity, costs, &line)
+                .into_iter()
+                .map(Keep)
+                .collect(),
+            Insert(line) => line_tokens(granularity, costs, &line)
+                .into_iter()
+                .map(Insert)
+                .collect(),
+            Delete(line) => line_tokens(granularity, costs, &line)
+                .into_iter()
+                .map(Delete)
+                .collect(),
+            // lines are aligned without transpositions, even with the Damerau algorithm
+            Substitute(line1, line2) | Transpose(line1, line2) => substituted_line_operations(
+                split_size.clone(),
+                granularity,
+                algorithm,
+                costs,
+                &line1,
+                &line2,
+            ),
+        };
+        result.push((line_operation, operations));
+    }
+    result
+}
+
+// Return the operations on the tokens of 2 lines which are different.
+// When the case or the normalization of the texts is ignored, equivalent pieces of text can be made
+// of a different number of chars: "ß" and "SS", "é" and "e\u{301}". In that case the lines are compared
+// grapheme by grapheme, and the runs of differences which are equivalent as a whole are kept
+fn substituted_line_operations(
+    split_size: SplitSize,
+    granularity: &Granularity,
+    algorithm: Algorithm,
+    costs: ComparisonCosts,
+    line1: &str,
+    line2: &str,
+) -> Vec<EditOperation<String>> {
+    let folded =
+        (costs.ignore_case || costs.normalization.is_some()) && !is_multi_chars(granularity);
+    let granularity = if folded {
+        &Granularity::Graphemes
+    } else {
+        granularity
+    };
+    let operations = line_operations(
+        split_size,
+        algorithm,
+        costs,
+        line_tokens(granularity, costs, line1),
+        line_tokens(granularity, costs, line2),
+    );
+    if folded {
+        keep_equivalent_runs(costs, operations)
+    } else {
+        operations
+    }
+}
+
+// Replace each run of differences with a kept text when the texts on both sides of the run are equivalent
+fn keep_equivalent_runs(
+    costs: ComparisonCosts,
+    operations: Vec<EditOperation<String>>,
+) -> Vec<EditOperation<String>> {
+    let mut result = vec![];
+    let mut run = vec![];
+    for operation in operations {
+        match operation {
+            Keep(token) => {
+                push_run(costs, &mut run, &mut result);
+                result.push(Keep(token));
+            }
+            difference => run.push(difference),
+        }
+    }
+    push_run(costs, &mut run, &mut result);
+    result
+}
+
+// Move a run of differences to the result, as a kept text if both sides of the run are equivalent
+fn push_run(
+    costs: ComparisonCosts,
+    run: &mut Vec<EditOperation<String>>,
+    result: &mut Vec<EditOperation<String>>,
+) {
+    if run.is_empty() {
+        return;
+    }
+    let (text1, text2) = run_texts(run);
+    if costs.equivalent(&text1, &text2) {
+        run.clear();
+        result.push(Keep(text1));
+    } else {
+        result.append(run);
+    }
+}
+
+// Return the texts of a run of differences, as found in the first and the second lines
+fn run_texts(run: &[EditOperation<String>]) -> (String, String) {
+    let mut text1 = String::new();
+    let mut text2 = String::new();
+    for operation in run {
+        match operation {
+            Insert(t) => text2.push_str(t),
+            Delete(t) => text1.push_str(t),
+            Substitute(t1, t2) => {
+                text1.push_str(t1);
+                text2.push_str(t2);
+            }
+            Transpose(t1, t2) => {
+                text1.push_str(t1);
+                text1.push_str(t2);
+                text2.push_str(t2);
+                text2.push_str(t1);
+            }
+            Keep(t) => {
+                text1.push_str(t);
+                text2.push_str(t);
+            }
+        }
+    }
+    (text1, text2)
+}
+
+// Split a line into tokens.
+// When the amount of whitespace is ignored, each run of whitespace is a single token
+// which is compared as a whole, whatever the granularity
+fn line_tokens(granularity: &Granularity, costs: ComparisonCosts, line: &str) -> Vec<String> {
+    let tokens = tokenize(granularity, line);
+    if costs.ignore_whitespace_amount {
+        merge_whitespace(tokens)
+    } else {
+        tokens
     }
-    result.concat()
+}
+
+// Show the differences between 2 pieces of text as a unified diff
+pub fn show_unified_diff<S1: Into<String>, S2: Into<String>>(s1: S1, s2: S2) -> String {
+    show_unified_diff_with(
+        default_display_options(),
+        default_unified_diff_options(),
+        s1,
+        s2,
+    )
+}
+
+// Show the differences between 2 pieces of text as a unified diff.
+// The lines are compared with the algorithms and comparison costs of the display options
+pub fn show_unified_diff_with<S1: Into<String>, S2: Into<String>>(
+    display_options: DisplayOptions,
+    unified_diff_options: UnifiedDiffOptions,
+    s1: S1,
+    s2: S2,
+) -> String {
+    // the line breaks are kept to show if the last line of a text has one
+    let lines_with_breaks = |s: String| -> Vec<String> {
+        s.split_inclusive('\n')
+            .map(|line| line.to_string())
+            .collect()
+    };
+    let operations = lines_operations(
+        display_options.algorithm,
+        display_options.line_algorithm,
+        display_options.comparison_costs,
+        lines_with_breaks(s1.into()),
+        lines_with_breaks(s2.into()),
+    );
+    display_unified_diff(unified_diff_options, operations)
 }
 
 // Size to use when splitting a large piece of text
@@ -44,64 +285,281 @@ fn default_split_size() -> SplitSize {
     SplitSize { split_size: 200 }
 }
 
-// Return the list of operations necessary to go from one piece of text to another
-// using the Levenshtein distance
-fn levenshtein_operations(s1: String, s2: String) -> Vec<EditOperation<char>> {
-    let matrix = create_edit_matrix(&levenshtein_costs(), s1.clone(), s2.clone());
-    make_edit_operations(s1.chars().collect(), s2.chars().collect(), matrix)
+// Return the list of operations necessary to go from one list of tokens to another
+// using a given algorithm and some comparison costs
+fn tokens_operations(
+    algorithm: Algorithm,
+    costs: ComparisonCosts,
+    ts1: Vec<String>,
+    ts2: Vec<String>,
+) -> Vec<EditOperation<String>> {
+    algorithm_edit_operations(algorithm, &ts1, &ts2, &costs)
 }
 
-// Split texts and apply the difference on each part
-// Split a text on newlines then split each line on a maximum split size
-// We then perform the edit distance algorithm on smaller sizes of text in order to control memory and CPU
-fn split(split_size: SplitSize, s: String) -> Vec<String> {
-    s.split('\n')
-        .map(|s2| split_to_size(split_size.clone(), s2.to_string()))
-        .collect::<Vec<Vec<String>>>()
-        .concat()
+// Return the list of operations necessary to go from one list of lines to another
+// Lines are either kept, inserted, deleted or substituted as a whole.
+// They are aligned with the line algorithm if there is one, otherwise with the Myers algorithm
+// when it is selected and with Levenshtein in the other cases
+fn lines_operations(
+    algorithm: Algorithm,
+    line_algorithm: Option<LineAlgorithm>,
+    costs: ComparisonCosts,
+    lines1: Vec<String>,
+    lines2: Vec<String>,
+) -> Vec<EditOperation<String>> {
+    match (line_algorithm, algorithm) {
+        (Some(line_algorithm), _) => {
+            // lines are compared with the form used by the comparison costs
+            let normalized = |lines: &[String]| -> Vec<String> {
+                lines.iter().map(|l| costs.normalize(l)).collect()
+            };
+            let (keys1, keys2) = (normalized(&lines1), normalized(&lines2));
+            let operations = line_algorithm_edit_operations(line_algorithm, &keys1, &keys2);
+            restore_lines(operations, &lines1, &lines2)
+        }
+        (None, Algorithm::Myers) => {
+            myers_edit_operations(&lines1, &lines2, &lines_costs_with(costs))
+        }
+        (None, _) => edit_operations(&lines1, &lines2, &lines_costs_with(costs)),
+    }
 }
 
-// Split a text on a maximum split size
-fn split_to_size(split_size: SplitSize, s: String) -> Vec<String> {
-    let n = split_size.split_size;
-    if s.len() <= n {
-        vec![s]
-    } else {
-        let mut result: Vec<String> = vec![];
-        result.push(s.chars().take(n).collect());
-        result.extend(split_to_size(split_size, s.chars().skip(n).collect()));
-        result
+// Replace the lines of some edit operations with the lines at the same positions in the original texts
+fn restore_lines(
+    operations: Vec<EditOperation<String>>,
+    lines1: &[String],
+    lines2: &[String],
+) -> Vec<EditOperation<String>> {
+    let (mut i, mut j) = (0, 0);
+    let mut result: Vec<EditOperation<String>> = vec![];
+    for operation in operations {
+        match operation {
+            Insert(_) => {
+                result.push(Insert(lines2[j].clone()));
+                j += 1;
+            }
+            Delete(_) => {
+                result.push(Delete(lines1[i].clone()));
+                i += 1;
+            }
+            // the line algorithms never transpose lines
+            Substitute(_, _) | Transpose(_, _) => {
+                result.push(Substitute(lines1[i].clone(), lines2[j].clone()));
+                (i, j) = (i + 1, j + 1);
+            }
+            Keep(_) => {
+                result.push(Keep(lines1[i].clone()));
+                (i, j) = (i + 1, j + 1);
+            }
+        }
     }
+    result
+}
+
+// Return the list of operations necessary to go from the tokens of one line to the tokens of another.
+// In order to control memory and CPU the edit distance algorithm is performed on windows
+// of at most split size tokens on each side.
+// Only the operations for the first half of the windows are kept, since the end of a window can be
+// cut in the middle of a change, then the next windows start right after the kept operations.
+// This way the windows are resynchronized after each insertion or deletion
+// The Myers algorithm only uses linear memory so the tokens are not split in that case
+fn line_operations(
+    split_size: SplitSize,
+    algorithm: Algorithm,
+    costs: ComparisonCosts,
+    ts1: Vec<String>,
+    ts2: Vec<String>,
+) -> Vec<EditOperation<String>> {
+    if algorithm == Algorithm::Myers {
+        return tokens_operations(algorithm, costs, ts1, ts2);
+    }
+    let n = split_size.split_size.max(1);
+    let mut result: Vec<EditOperation<String>> = vec![];
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let end1 = (i + n).min(ts1.len());
+        let end2 = (j + n).min(ts2.len());
+        let operations = tokens_operations(
+            algorithm,
+            costs,
+            ts1[i..end1].to_vec(),
+            ts2[j..end2].to_vec(),
+        );
+        if end1 == ts1.len() && end2 == ts2.len() {
+            result.extend(operations);
+            return result;
+        }
+        let (kept, consumed1, consumed2) = first_half(&operations, n.div_ceil(2));
+        result.extend(operations.into_iter().take(kept));
+        i += consumed1;
+        j += consumed2;
+    }
+}
+
+// Return the smallest number of operations consuming at least half tokens from one of the 2 windows,
+// and the number of tokens consumed from each window by those operations
+fn first_half(operations: &[EditOperation<String>], half: usize) -> (usize, usize, usize) {
+    let (mut consumed1, mut consumed2) = (0, 0);
+    for (index, operation) in operations.iter().enumerate() {
+        match operation {
+            Insert(_) => consumed2 += 1,
+            Delete(_) => consumed1 += 1,
+            Transpose(_, _) => (consumed1, consumed2) = (consumed1 + 2, consumed2 + 2),
+            Substitute(_, _) | Keep(_) => (consumed1, consumed2) = (consumed1 + 1, consumed2 + 1),
+        }
+        if consumed1 >= half || consumed2 >= half {
+            return (index + 1, consumed1, consumed2);
+        }
+    }
+    (operations.len(), consumed1, consumed2)
+}
+
+// Split a text on newlines
+fn lines(s: String) -> Vec<String> {
+    s.split('\n').map(|line| line.to_string()).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use EditOperation::*;
+    use proptest::prelude::*;
+    use std::sync::Arc;
 
+    fn chars(s: &str) -> Vec<String> {
+        tokenize(&Granularity::Chars, s)
+    }
+    fn t(s: &str) -> String {
+        s.to_string()
+    }
+
+    #[test]
+    fn test_tokens_operations() {
+        assert_eq!(
+            tokens_operations(
+                Algorithm::Levenshtein,
+                comparison_costs(),
+                chars("kitte"),
+                chars("kittei")
+            ),
+            vec![
+                Keep(t("k")),
+                Keep(t("i")),
+                Keep(t("t")),
+                Keep(t("t")),
+                Keep(t("e")),
+                Insert(t("i"))
+            ]
+        );
+    }
+    #[test]
+    fn test_lines_operations() {
+        let to_lines = |ls: Vec<&str>| ls.iter().map(|l| l.to_string()).collect();
+        assert_eq!(
+            lines_operations(
+                Algorithm::Levenshtein,
+                None,
+                comparison_costs(),
+                to_lines(vec!["a", "b", "c"]),
+                to_lines(vec!["a", "c", "d"])
+            ),
+            vec![
+                Keep("a".to_string()),
+                Delete("b".to_string()),
+                Keep("c".to_string()),
+                Insert("d".to_string())
+            ]
+        );
+    }
     #[test]
-    fn test_split_to_size() {
-        let split_size = SplitSize { split_size: 5 };
+    fn test_transposed_lines() {
+        // swapped lines are aligned with insertions and deletions, even with the Damerau algorithm
+        let mut options = default_display_options();
+        options.algorithm = Algorithm::Damerau;
         assert_eq!(
-            split_to_size(split_size, "abcdefghij".to_string()),
-            vec!["abcde".to_string(), "fghij".to_string()]
+            show_distance_with(default_split_size(), options, "a\nb\nc", "a\nc\nb"),
+            "a\n[-b]\nc\n[+b]"
         );
     }
     #[test]
-    fn test_levenshtein_operations() {
+    fn test_line_operations() {
+        let split_size = SplitSize { split_size: 2 };
         assert_eq!(
-            levenshtein_operations("kitte".to_string(), "kittei".to_string()),
+            line_operations(
+                split_size,
+                Algorithm::Levenshtein,
+                comparison_costs(),
+                chars("abc"),
+                chars("adcef")
+            ),
             vec![
-                Keep('k'),
-                Keep('i'),
-                Keep('t'),
-                Keep('t'),
-                Keep('e'),
-                Insert('i')
+                Keep(t("a")),
+                Substitute(t("b"), t("d")),
+                Keep(t("c")),
+                Insert(t("e")),
+                Insert(t("f"))
             ]
         );
     }
     #[test]
+    fn test_line_operations_resynchronize() {
+        // a char inserted at the start of a long line does not shift the following windows
+        let text = "the quick brown fox jumps over the lazy dog ".repeat(25);
+        let modified = format!("{}*{}", &text[..10], &text[10..]);
+        let operations = line_operations(
+            default_split_size(),
+            Algorithm::Levenshtein,
+            comparison_costs(),
+            chars(&text),
+            chars(&modified),
+        );
+        let changes: Vec<&EditOperation<String>> = operations
+            .iter()
+            .filter(|o| !matches!(o, Keep(_)))
+            .collect();
+        assert_eq!(changes, vec![&Insert(t("*"))]);
+
+        let diff = show_distance(text.clone(), format!("{text}!"));
+        assert_eq!(diff.matches('[').count(), 1);
+        assert!(diff.ends_with("dog [+!]"));
+    }
+    #[test]
+    fn test_show_distance_lines() {
+        // a deleted line does not shift the following lines
+        assert_eq!(show_distance("a\nb\nc", "a\nc"), "a\n[-b]\nc");
+        // an inserted line does not shift the following lines
+        assert_eq!(show_distance("a\nc", "a\nb\nc"), "a\n[+b]\nc");
+        // extra trailing lines are reported
+        assert_eq!(
+            show_distance("abc\nxyz", "abd\nxyz\nnew"),
+            "ab[~c/d]\nxyz\n[+n+e+w]"
+        );
+        assert_eq!(show_distance("abc\nxyz", "abc"), "abc\n[-x-y-z]");
+    }
+    #[test]
+    fn test_show_distance_empty_lines() {
+        // a blank line which is deleted or inserted is reported
+        assert_eq!(show_distance("a\n\nb", "a\nb"), "a\n[-]\nb");
+        assert_eq!(show_distance("a\nb", "a\n\nb"), "a\n[+]\nb");
+        assert_eq!(show_distance_inverse("a\n\nb", "a\nb"), "a\n[+]\nb");
+        // a missing or extra trailing new line is reported
+        assert_eq!(show_distance("a\n", "a"), "a\n[-]");
+        assert_eq!(show_distance("a", "a\n"), "a\n[+]");
+        assert_eq!(show_distance_inverse("a", "a\n"), "a\n[-]");
+        assert_eq!(show_distance("a\n", "a\n"), "a\n");
+    }
+    #[test]
+    fn test_show_distance_line_numbers() {
+        let mut options = default_display_options();
+        options.line_options = LineOptions {
+            show_line_numbers: true,
+            show_line_markers: true,
+        };
+        assert_eq!(
+            show_distance_with(default_split_size(), options, "a\nb\nc", "a\nB\nc\nd"),
+            "1   a\n2 ~ [~b/B]\n3   c\n  + [+d]"
+        );
+    }
+    #[test]
     fn test_show_distance() {
         assert_eq!(show_distance("k", "l"), "[~k/l]");
         assert_eq!(show_distance("ki", "ka"), "k[~i/a]");
@@ -111,5 +569,296 @@ mod tests {
         assert_eq!(show_distance("kit", "kitten"), "kit[+t+e+n]");
         assert_eq!(show_distance("kitten", "kitsin"), "kit[~t/s~e/i]n");
         assert_eq!(show_distance("kitte", "kitte"), "kitte");
+        assert_eq!(show_distance("", "abc"), "[+a+b+c]");
+        assert_eq!(show_distance("abc", ""), "[-a-b-c]");
+        assert_eq!(show_distance("", ""), "");
+    }
+    #[test]
+    fn test_show_distance_unicode() {
+        assert_eq!(show_distance("café", "cafe"), "caf[~é/e]");
+        assert_eq!(show_distance("日本語", "日本"), "日本[-語]");
+        assert_eq!(show_distance("a😀b", "ab"), "a[-😀]b");
+        // e + combining acute accent
+        let mut options = default_display_options();
+        options.granularity = Granularity::Graphemes;
+        assert_eq!(
+            show_distance_with(default_split_size(), options, "cafe\u{301}", "cafe"),
+            "caf[~e\u{301}/e]"
+        );
+        assert_eq!(show_distance("cafe\u{301}", "cafe"), "cafe[-\u{301}]");
+    }
+    #[test]
+    fn test_show_distance_inverse() {
+        assert_eq!(show_distance_inverse("kitten", "kitsin"), "kit[~s/t~i/e]n");
+        assert_eq!(show_distance_inverse("kitten", "kit"), "kit[+t+e+n]");
+        assert_eq!(show_distance_inverse("a\nb", "a"), "a\n[+b]");
+        assert_eq!(
+            show_distance_both("kitten", "kittein"),
+            "expected   | actual\nkitte[+i]n | kitte[-i]n"
+        );
+    }
+    #[test]
+    fn test_show_distance_colored() {
+        assert_eq!(
+            show_distance_colored_with(ColorChoice::Always, "kitten", "kitsen"),
+            "kit[\x1b[36mt\x1b[0m]en"
+        );
+        assert_eq!(
+            show_distance_colored_with(ColorChoice::Never, "kitten", "kitsen"),
+            "kit[~t/s]en"
+        );
+    }
+
+    #[test]
+    fn test_show_distance_damerau() {
+        let mut options = default_display_options();
+        options.algorithm = Algorithm::Damerau;
+        assert_eq!(
+            show_distance_with(default_split_size(), options.clone(), "teh cat", "the cat"),
+            "t[e<>h] cat"
+        );
+        assert_eq!(
+            show_distance_with(default_split_size(), options.clone(), "kitten", "kitsin"),
+            "kit[~t/s~e/i]n"
+        );
+        assert_eq!(show_distance("teh cat", "the cat"), "t[~e/h~h/e] cat");
+
+        // the transposed elements can differ by ignored differences
+        options.comparison_costs.ignore_case = true;
+        assert_eq!(
+            show_distance_with(default_split_size(), options, "Ab", "bA"),
+            "[A<>b]"
+        );
+    }
+    #[test]
+    fn test_show_distance_patience() {
+        let before = "f() {\n  one();\n}\ng() {\n  two();\n}";
+        let after = "g() {\n  two();\n}\nh() {\n  three();\n}";
+        let mut options = default_display_options();
+        options.line_options.show_line_markers = true;
+        let show = |options: DisplayOptions, s1: &str, s2: &str| {
+            show_distance_with(default_split_size(), options, s1, s2)
+        };
+        // with the Levenshtein algorithm the braces are aligned and all the other lines are modified
+        assert_eq!(
+            show(options.clone(), before, after),
+            "~ [~f/g]() {\n~   [~o/t~n/w~e/o]();\n  }\n~ [~g/h]() {\n~   t[+h+r~w/e~o/e]();\n  }"
+        );
+        for line_algorithm in [LineAlgorithm::Patience, LineAlgorithm::Histogram] {
+            options.line_algorithm = Some(line_algorithm);
+            assert_eq!(
+                show(options.clone(), before, after),
+                "- [-f-(-)- -{]\n- [- - -o-n-e-(-)-;]\n- [-}]\n  g() {\n    two();\n+ [+}]\n+ [+h+(+)+ +{]\n+ [+ + +t+h+r+e+e+(+)+;]\n  }"
+            );
+        }
+        // lines are compared with the comparison costs
+        options.comparison_costs.ignore_case = true;
+        assert_eq!(show(options, "A\nb\nc", "a\nB\nd"), "  A\n  b\n~ [~c/d]");
+    }
+    #[test]
+    fn test_show_unified_diff() {
+        assert_eq!(show_unified_diff("a\nb\nc\n", "a\nb\nc\n"), "");
+        assert_eq!(
+            show_unified_diff("a\nb\nc\n", "a\nB\nc\nd"),
+            "--- a\n+++ b\n@@ -1,3 +1,4 @@\n a\n-b\n+B\n c\n+d\n\\ No newline at end of file\n"
+        );
+        let mut display_options = default_display_options();
+        display_options.comparison_costs.ignore_case = true;
+        let mut unified_diff_options = default_unified_diff_options();
+        unified_diff_options.old_name = "old.txt".to_string();
+        unified_diff_options.new_name = "new.txt".to_string();
+        unified_diff_options.context_lines = 0;
+        assert_eq!(
+            show_unified_diff_with(
+                display_options,
+                unified_diff_options,
+                "a\nb\nc\n",
+                "a\nB\nx\n"
+            ),
+            "--- old.txt\n+++ new.txt\n@@ -3 +3 @@\n-c\n+x\n"
+        );
+    }
+    #[test]
+    fn test_show_side_by_side() {
+        let options = SideBySideOptions { width: Some(23) };
+        assert_eq!(
+            show_side_by_side_with(
+                default_split_size(),
+                default_display_options(),
+                options,
+                "a\nbc\nd",
+                "a\nbx\ne\nd"
+            ),
+            "a            a\nb[-c]      | b[+x]\n           > e\nd            d"
+        );
+    }
+    #[test]
+    fn test_show_distance_myers() {
+        let mut options = default_display_options();
+        options.algorithm = Algorithm::Myers;
+        let show =
+            |s1: &str, s2: &str| show_distance_with(default_split_size(), options.clone(), s1, s2);
+        assert_eq!(show("kitten", "kitsin"), "kit[~t/s~e/i]n");
+        assert_eq!(show("a\nb\nc", "a\nc\nd"), "a\n[-b]\nc\n[+d]");
+        assert_eq!(show("abc\nxyz", "abd\nxyz\nnew"), "ab[~c/d]\nxyz\n[+n+e+w]");
+
+        // large texts are not split in chunks, so a deleted char does not shift the following chunks
+        let text: String = "the quick brown fox ".repeat(5000);
+        let diff = show(&format!("x{text}"), &format!("{text}y"));
+        assert_eq!(diff.matches('[').count(), 2);
+        assert!(diff.starts_with("[-x]the"));
+        assert!(diff.ends_with("fox [+y]"));
+    }
+    #[test]
+    fn test_show_distance_ignoring_differences() {
+        let show =
+            |comparison_costs: ComparisonCosts, granularity: Granularity, s1: &str, s2: &str| {
+                let mut options = default_display_options();
+                options.comparison_costs = comparison_costs;
+                options.granularity = granularity;
+                show_distance_with(default_split_size(), options, s1, s2)
+            };
+        let mut costs = comparison_costs();
+        costs.ignore_case = true;
+        assert_eq!(show(costs, Granularity::Chars, "Hello", "hellO"), "Hello");
+        assert_eq!(
+            show(costs, Granularity::Chars, "Hello", "hallO"),
+            "H[~e/a]llo"
+        );
+        assert_eq!(
+            show(costs, Granularity::Chars, "a\nB", "A\nb\nc"),
+            "a\nB\n[+c]"
+        );
+
+        costs.ignore_whitespace_amount = true;
+        assert_eq!(
+            show(costs, Granularity::Chars, "a  b\tc", "a b c"),
+            "a  b\tc"
+        );
+        // inside a modified line, whitespace runs are kept whatever their size
+        assert_eq!(
+            show(costs, Granularity::Chars, "a  b x", "a b y"),
+            "a  b [~x/y]"
+        );
+        assert_eq!(
+            show(costs, Granularity::Chars, "a b x", "a \t b y"),
+            "a b [~x/y]"
+        );
+        // a run of whitespace which is added is still reported
+        assert_eq!(show(costs, Granularity::Chars, "ab", "a  b"), "a[+  ]b");
+        assert_eq!(
+            show(costs, Granularity::Tokens, "a  b\tc d", "a b c e"),
+            "a  b\tc [-d+e]"
+        );
+
+        costs.normalization = Some(Normalization::Canonical);
+        assert_eq!(
+            show(costs, Granularity::Graphemes, "café", "cafe\u{301}"),
+            "café"
+        );
+
+        // equivalent text is kept when there is another difference on the same line
+        let mut costs = comparison_costs();
+        costs.normalization = Some(Normalization::Canonical);
+        assert_eq!(
+            show(costs, Granularity::Chars, "café x", "cafe\u{301} y"),
+            "café [~x/y]"
+        );
+        let mut costs = comparison_costs();
+        costs.ignore_case = true;
+        assert_eq!(
+            show(costs, Granularity::Chars, "STRASSE a", "straße b"),
+            "STRASSE [~a/b]"
+        );
+        // the other graphemes are still compared one by one
+        assert_eq!(
+            show(costs, Granularity::Chars, "Hello x", "hallo y"),
+            "H[~e/a]llo [~x/y]"
+        );
+    }
+    #[test]
+    fn test_show_distance_words() {
+        let show = |granularity: Granularity, s1: &str, s2: &str| {
+            let mut options = default_display_options();
+            options.granularity = granularity;
+            show_distance_with(default_split_size(), options, s1, s2)
+        };
+        assert_eq!(
+            show(Granularity::Words, "the quick fox", "the slow fox"),
+            "the [-quick+slow] fox"
+        );
+        assert_eq!(
+            show(Granularity::Words, "the old word", "the new text!"),
+            "the [-old+new] [-word+text!]"
+        );
+        assert_eq!(show(Granularity::Tokens, "a-b c", "a-c c"), "[-a-b+a-c] c");
+        let commas = |s: &str| s.split_inclusive(',').map(|t| t.to_string()).collect();
+        assert_eq!(
+            show(Granularity::Custom(Arc::new(commas)), "a,b,c", "a,x y,c"),
+            "a,[-b,+x y,]c"
+        );
+        // long texts are shortened on tokens
+        assert_eq!(
+            show(
+                Granularity::Words,
+                "one two three four five six seven eight nine ten eleven twelve",
+                "one two three four five six seven eight nine ten eleven 12"
+            ),
+            "...two three four five six seven eight nine ten eleven [-twelve+12]"
+        );
+    }
+
+    // Return the source and the target of a list of edit operations
+    fn source_and_target(operations: Vec<EditOperation<String>>) -> (String, String) {
+        let (mut source, mut target) = (String::new(), String::new());
+        for operation in operations {
+            match operation {
+                Insert(t) => target.push_str(&t),
+                Delete(t) => source.push_str(&t),
+                Substitute(t1, t2) => {
+                    source.push_str(&t1);
+                    target.push_str(&t2)
+                }
+                Transpose(t1, t2) => {
+                    source.push_str(&t1);
+                    source.push_str(&t2);
+                    target.push_str(&t2);
+                    target.push_str(&t1)
+                }
+                Keep(t) => {
+                    source.push_str(&t);
+                    target.push_str(&t)
+                }
+            }
+        }
+        (source, target)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_levenshtein_operations_chars(s1 in "\\PC{0,20}", s2 in "\\PC{0,20}") {
+            let operations = tokens_operations(Algorithm::Levenshtein, comparison_costs(), chars(&s1), chars(&s2));
+            prop_assert_eq!(source_and_target(operations), (s1, s2));
+        }
+        #[test]
+        fn prop_levenshtein_operations_graphemes(s1 in "\\PC{0,20}", s2 in "\\PC{0,20}") {
+            let graphemes = |s: &str| tokenize(&Granularity::Graphemes, s);
+            let operations = tokens_operations(Algorithm::Levenshtein, comparison_costs(), graphemes(&s1), graphemes(&s2));
+            prop_assert_eq!(source_and_target(operations), (s1, s2));
+        }
+        #[test]
+        fn prop_line_operations(s1 in "[a-c]{0,30}", s2 in "[a-c]{0,30}", split_size in 1..10usize) {
+            let split_size = SplitSize { split_size };
+            let operations = line_operations(split_size, Algorithm::Levenshtein, comparison_costs(), chars(&s1), chars(&s2));
+            prop_assert_eq!(source_and_target(operations), (s1, s2));
+        }
+        #[test]
+        fn prop_show_distance_same_text(s in "\\PC{0,20}") {
+            prop_assert_eq!(show_distance(s.clone(), s.clone()), s);
+        }
+        #[test]
+        fn prop_show_distance_does_not_fail(s1 in "\\PC{0,100}", s2 in "\\PC{0,100}") {
+            show_distance(s1, s2);
+        }
     }
 }
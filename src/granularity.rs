@@ -0,0 +1,172 @@
+use std::fmt;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+use Granularity::*;
+
+// Unit used to split a piece of text into tokens before comparing it to another piece of text
+//  - Chars: each Unicode scalar value is a token
+//  - Graphemes: each extended grapheme cluster is a token, so that an accented letter
+//    made of several code points or an emoji with modifiers is compared as a whole
+//  - Words: words, whitespace and punctuation are separate tokens (Unicode word boundaries)
+//  - Tokens: runs of non-whitespace characters and runs of whitespace are separate tokens
+//  - Custom: tokens are produced by a user-supplied tokenizer, which can be a closure capturing some state.
+//    The concatenation of the tokens must be the original text
+#[derive(Clone)]
+pub enum Granularity {
+    Chars,
+    Graphemes,
+    Words,
+    Tokens,
+    Custom(Tokenizer),
+}
+
+// Function splitting a piece of text into tokens
+pub type Tokenizer = Arc<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+// Custom tokenizers are compared by identity, since functions can't be compared
+impl PartialEq for Granularity {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Custom(tokenizer1), Custom(tokenizer2)) => Arc::ptr_eq(tokenizer1, tokenizer2),
+            (Chars, Chars) | (Graphemes, Graphemes) | (Words, Words) | (Tokens, Tokens) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Granularity {}
+
+// The custom tokenizer can't be displayed, only its presence is shown
+impl fmt::Debug for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chars => write!(f, "Chars"),
+            Graphemes => write!(f, "Graphemes"),
+            Words => write!(f, "Words"),
+            Tokens => write!(f, "Tokens"),
+            Custom(_) => write!(f, "Custom(<tokenizer>)"),
+        }
+    }
+}
+
+// Default granularity
+pub fn default_granularity() -> Granularity {
+    Chars
+}
+
+// Split a piece of text into tokens
+pub fn tokenize(granularity: &Granularity, s: &str) -> Vec<String> {
+    match granularity {
+        Chars => s.chars().map(|c| c.to_string()).collect(),
+        Graphemes => s.graphemes(true).map(|g| g.to_string()).collect(),
+        Words => s.split_word_bounds().map(|w| w.to_string()).collect(),
+        Tokens => whitespace_tokens(s),
+        Custom(tokenizer) => tokenizer(s),
+    }
+}
+
+// Merge consecutive tokens made only of whitespace into a single token,
+// so that a run of whitespace can be compared as a whole
+pub fn merge_whitespace(tokens: Vec<String>) -> Vec<String> {
+    let is_whitespace = |t: &str| !t.is_empty() && t.chars().all(char::is_whitespace);
+    let mut result: Vec<String> = vec![];
+    for token in tokens {
+        match result.last_mut() {
+            Some(last) if is_whitespace(last) && is_whitespace(&token) => last.push_str(&token),
+            _ => result.push(token),
+        }
+    }
+    result
+}
+
+// Return true if the tokens are larger than a single character.
+// In that case the differences are displayed as a whole: [-old words+new words]
+pub fn is_multi_chars(granularity: &Granularity) -> bool {
+    match granularity {
+        Chars | Graphemes => false,
+        Words | Tokens | Custom(_) => true,
+    }
+}
+
+// Split a piece of text into runs of whitespace and runs of non-whitespace characters
+fn whitespace_tokens(s: &str) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    let mut current = String::new();
+    for c in s.chars() {
+        let same_kind = current
+            .chars()
+            .last()
+            .map(|last| last.is_whitespace() == c.is_whitespace())
+            .unwrap_or(true);
+        if !same_kind {
+            result.push(current);
+            current = String::new();
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_tokenize() {
+        // e + combining acute accent
+        let s = "cafe\u{301}";
+        assert_eq!(tokenize(&Chars, s), vec!["c", "a", "f", "e", "\u{301}"]);
+        assert_eq!(tokenize(&Graphemes, s), vec!["c", "a", "f", "e\u{301}"]);
+        assert_eq!(tokenize(&Graphemes, "日本"), vec!["日", "本"]);
+        assert_eq!(
+            tokenize(&Words, "the fox, jumps"),
+            vec!["the", " ", "fox", ",", " ", "jumps"]
+        );
+        assert_eq!(
+            tokenize(&Tokens, "the fox,  jumps"),
+            vec!["the", " ", "fox,", "  ", "jumps"]
+        );
+        let commas = |s: &str| s.split_inclusive(',').map(|t| t.to_string()).collect();
+        assert_eq!(
+            tokenize(&Custom(Arc::new(commas)), "a,b c"),
+            vec!["a,", "b c"]
+        );
+        // a tokenizer can capture its separator
+        let separator = ';';
+        let custom = Custom(Arc::new(move |s: &str| {
+            s.split_inclusive(separator)
+                .map(|t| t.to_string())
+                .collect()
+        }));
+        assert_eq!(tokenize(&custom, "a;b,c"), vec!["a;", "b,c"]);
+        assert_eq!(format!("{:?}", custom), "Custom(<tokenizer>)");
+        assert_eq!(custom.clone(), custom);
+        assert_ne!(custom, Custom(Arc::new(|s: &str| vec![s.to_string()])));
+    }
+
+    #[test]
+    fn test_merge_whitespace() {
+        assert_eq!(
+            merge_whitespace(tokenize(&Chars, "a  b\t c")),
+            vec!["a", "  ", "b", "\t ", "c"]
+        );
+        assert_eq!(
+            merge_whitespace(tokenize(&Tokens, "a  b")),
+            tokenize(&Tokens, "a  b")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_tokenize_preserves_text(s in "\\PC*") {
+            prop_assert_eq!(tokenize(&Chars, &s).concat(), s.clone());
+            prop_assert_eq!(tokenize(&Graphemes, &s).concat(), s.clone());
+            prop_assert_eq!(tokenize(&Words, &s).concat(), s.clone());
+            prop_assert_eq!(tokenize(&Tokens, &s).concat(), s);
+        }
+    }
+}
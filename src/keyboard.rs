@@ -0,0 +1,178 @@
+use crate::costs::*;
+use crate::distance::*;
+use crate::similarity::*;
+use KeyboardLayout::*;
+
+// Keyboard layouts which can be used to evaluate how close 2 keys are
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Azerty,
+    Dvorak,
+}
+
+// Rows of keys, from the number row to the bottom row.
+// Each row is given as its unshifted and shifted characters, plus the horizontal position
+// of its first key, in quarters of a key, to account for the stagger of the rows
+type KeyboardRow = (&'static str, &'static str, usize);
+
+const QWERTY_ROWS: [KeyboardRow; 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+", 0),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|", 6),
+    ("asdfghjkl;'", "ASDFGHJKL:\"", 7),
+    ("zxcvbnm,./", "ZXCVBNM<>?", 9),
+];
+
+const AZERTY_ROWS: [KeyboardRow; 4] = [
+    ("²&é\"'(-è_çà)=", "²1234567890°+", 0),
+    ("azertyuiop^$", "AZERTYUIOP¨£", 6),
+    ("qsdfghjklmù*", "QSDFGHJKLM%µ", 7),
+    ("<wxcvbn,;:!", ">WXCVBN?./§", 5),
+];
+
+const DVORAK_ROWS: [KeyboardRow; 4] = [
+    ("`1234567890[]", "~!@#$%^&*(){}", 0),
+    ("',.pyfgcrl/=\\", "\"<>PYFGCRL?+|", 6),
+    ("aoeuidhtns-", "AOEUIDHTNS_", 7),
+    (";qjkxbmwvz", ":QJKXBMWVZ", 9),
+];
+
+// Cost of inserting or deleting a character.
+// Substituting a character with a neighbouring key, or with the same key shifted, costs half of it
+const KEY_COST: usize = 2;
+
+// Implementation of the Costs trait for characters typed on a keyboard:
+// substituting 2 adjacent keys is cheaper than substituting 2 distant keys, so that
+// typos like "cst" for "cat" are considered closer than "cpt"
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct KeyboardCosts {
+    pub layout: KeyboardLayout,
+}
+
+impl Costs<char> for KeyboardCosts {
+    fn insertion_cost(self, _t: &char) -> usize {
+        KEY_COST
+    }
+    fn deletion_cost(self, _t: &char) -> usize {
+        KEY_COST
+    }
+    fn substitution_cost(self, t1: &char, t2: &char) -> usize {
+        if t1 == t2 {
+            0
+        } else if are_adjacent_keys(self.layout, *t1, *t2) {
+            KEY_COST / 2
+        } else {
+            KEY_COST
+        }
+    }
+    fn transposition_cost(self, _t1: &char, _t2: &char) -> usize {
+        KEY_COST
+    }
+    fn lower_cost(self, t1: &char, t2: &char, ins: usize, del: usize, sub: usize) -> Cost {
+        levenshtein_costs().lower_cost(t1, t2, ins, del, sub)
+    }
+}
+
+pub fn keyboard_costs(layout: KeyboardLayout) -> KeyboardCosts {
+    KeyboardCosts { layout }
+}
+
+impl Metric for KeyboardCosts {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        keyboard_similarity(self.layout, s1, s2)
+    }
+}
+
+// Return a similarity score between 0.0 (completely different) and 1.0 (identical)
+// based on the keyboard distance between 2 strings
+pub fn keyboard_similarity(layout: KeyboardLayout, s1: &str, s2: &str) -> f64 {
+    let cs1: Vec<char> = s1.chars().collect();
+    let cs2: Vec<char> = s2.chars().collect();
+    let max_distance = KEY_COST * cs1.len().max(cs2.len());
+    if max_distance == 0 {
+        1.0
+    } else {
+        let distance = distance_with(&keyboard_costs(layout), &cs1, &cs2);
+        1.0 - distance as f64 / max_distance as f64
+    }
+}
+
+// Return true if 2 characters are typed with the same key or with 2 keys next to each other
+pub fn are_adjacent_keys(layout: KeyboardLayout, c1: char, c2: char) -> bool {
+    match (key_position(layout, c1), key_position(layout, c2)) {
+        (Some((row1, x1)), Some((row2, x2))) => row1.abs_diff(row2) <= 1 && x1.abs_diff(x2) <= 4,
+        _ => false,
+    }
+}
+
+// Return the row of a key and its horizontal position in quarters of a key
+fn key_position(layout: KeyboardLayout, c: char) -> Option<(usize, usize)> {
+    let rows = match layout {
+        Qwerty => QWERTY_ROWS,
+        Azerty => AZERTY_ROWS,
+        Dvorak => DVORAK_ROWS,
+    };
+    rows.iter()
+        .enumerate()
+        .find_map(|(row, (unshifted, shifted, offset))| {
+            unshifted
+                .chars()
+                .position(|k| k == c)
+                .or_else(|| shifted.chars().position(|k| k == c))
+                .map(|column| (row, offset + 4 * column))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_operation::EditOperation::*;
+    use crate::edit_operation::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_are_adjacent_keys() {
+        assert!(are_adjacent_keys(Qwerty, 'a', 's'));
+        assert!(are_adjacent_keys(Qwerty, 'a', 'q'));
+        assert!(are_adjacent_keys(Qwerty, 'a', 'A'));
+        assert!(are_adjacent_keys(Qwerty, 's', 'E'));
+        assert!(!are_adjacent_keys(Qwerty, 'a', 'p'));
+        assert!(!are_adjacent_keys(Qwerty, 's', 'q'));
+        assert!(!are_adjacent_keys(Qwerty, 'a', '€'));
+
+        assert!(!are_adjacent_keys(Qwerty, 'm', 'l'));
+        assert!(are_adjacent_keys(Azerty, 'm', 'l'));
+        assert!(!are_adjacent_keys(Qwerty, 'a', 'o'));
+        assert!(are_adjacent_keys(Dvorak, 'a', 'o'));
+    }
+
+    #[test]
+    fn test_keyboard_distance() {
+        let costs = keyboard_costs(Qwerty);
+        assert_eq!(distance_with(&costs, &chars("cat"), &chars("cat")), 0);
+        assert_eq!(distance_with(&costs, &chars("cat"), &chars("cst")), 1);
+        assert_eq!(distance_with(&costs, &chars("cat"), &chars("cpt")), 2);
+        assert_eq!(distance_with(&costs, &chars("cat"), &chars("cats")), 2);
+        assert_eq!(
+            edit_operations(&chars("cat"), &chars("cst"), &costs),
+            vec![Keep('c'), Substitute('a', 's'), Keep('t')]
+        );
+    }
+
+    #[test]
+    fn test_keyboard_similarity() {
+        assert_eq!(keyboard_similarity(Qwerty, "", ""), 1.0);
+        assert_eq!(keyboard_similarity(Qwerty, "cat", "cat"), 1.0);
+        assert_eq!(keyboard_similarity(Qwerty, "abc", ""), 0.0);
+        assert_eq!(keyboard_similarity(Qwerty, "ab", "sb"), 0.75);
+        assert_eq!(keyboard_similarity(Qwerty, "ab", "pb"), 0.5);
+        assert!(
+            keyboard_similarity(Qwerty, "cat", "cst") > keyboard_similarity(Qwerty, "cat", "cpt")
+        );
+        assert_eq!(keyboard_similarity(Azerty, "ma", "la"), 0.75);
+        assert_eq!(keyboard_costs(Azerty).normalized_distance("ma", "la"), 0.25);
+    }
+}
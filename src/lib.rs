@@ -15,12 +15,21 @@
 /// let my_split_size = SplitSize { split_size: 300 };
 /// assert_eq!(show_distance_with(my_split_size, default_display_options(), "kitten", "kitsin"), "kit[~t/s~e/i]n");
 /// ```
+pub mod algorithm;
 pub mod color;
 pub mod costs;
 pub mod difference;
+pub mod distance;
 pub mod edit_matrix;
 pub mod edit_operation;
 pub mod edits;
+pub mod granularity;
+pub mod keyboard;
 pub mod matrix;
+pub mod myers;
+pub mod patience;
+pub mod renderer;
 pub mod shorten;
+pub mod side_by_side;
+pub mod similarity;
 pub mod token;
@@ -1,28 +1,67 @@
 use core::mem::*;
 
+// A matrix of values stored row by row in a single buffer
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Matrix<T: Sized> {
-    pub rows: Vec<Vec<T>>,
+    pub rows_nb: usize,
+    pub cols_nb: usize,
+    pub values: Vec<T>,
 }
 
 impl<T> Matrix<T> {
     // Return a value if it exists at coordinates i and j
     pub fn get_value(&self, i: usize, j: usize) -> Option<&T> {
-        self.rows.get(i)?.get(j)
+        if i < self.rows_nb && j < self.cols_nb {
+            self.values.get(i * self.cols_nb + j)
+        } else {
+            None
+        }
     }
 
     // Set a value at coordinates i and j.
     // Returns Some(())) if there is such a value and None otherwise
     pub fn set_value(&mut self, i: usize, j: usize, t: T) -> Option<T> {
-        self.rows
-            .get_mut(i)
-            .and_then(|v| v.get_mut(j))
-            .map(|val| replace(val, t))
+        if i < self.rows_nb && j < self.cols_nb {
+            self.values
+                .get_mut(i * self.cols_nb + j)
+                .map(|val| replace(val, t))
+        } else {
+            None
+        }
     }
 }
 
 pub fn init_matrix<T: Clone>(rows_nb: usize, cols_nb: usize, t: T) -> Matrix<T> {
     Matrix {
-        rows: vec![vec![t; cols_nb]; rows_nb],
+        rows_nb,
+        cols_nb,
+        values: vec![t; rows_nb * cols_nb],
+    }
+}
+
+// Create a matrix from a list of rows having all the same size
+pub fn matrix_from_rows<T>(rows: Vec<Vec<T>>) -> Matrix<T> {
+    let rows_nb = rows.len();
+    let cols_nb = rows.first().map(|row| row.len()).unwrap_or(0);
+    Matrix {
+        rows_nb,
+        cols_nb,
+        values: rows.into_iter().flatten().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set_value() {
+        let mut matrix = init_matrix(2, 3, 0);
+        assert_eq!(matrix.set_value(1, 2, 5), Some(0));
+        assert_eq!(matrix.get_value(1, 2), Some(&5));
+        assert_eq!(matrix, matrix_from_rows(vec![vec![0, 0, 0], vec![0, 0, 5]]));
+        // out of bounds coordinates
+        assert_eq!(matrix.get_value(0, 3), None);
+        assert_eq!(matrix.set_value(2, 0, 1), None);
     }
 }
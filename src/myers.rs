@@ -0,0 +1,315 @@
+use crate::costs::*;
+use crate::edit_operation::*;
+use EditOperation::*;
+
+// Return the list of operations necessary to go from one list of tokens to another
+// with Myers' O(ND) diff algorithm, in its linear space version.
+// This is much faster than computing an edit matrix for large texts with few differences,
+// and it only uses O(n + m) memory, so the texts don't need to be split in chunks.
+//
+// Tokens are only inserted, deleted or kept, with 2 tokens being equal when their substitution cost is 0.
+// Then in each run of changes the deleted and inserted tokens are paired as substitutions
+pub fn myers_edit_operations<T: PartialEq + Clone>(
+    ts1: &[T],
+    ts2: &[T],
+    costs: &impl Costs<T>,
+) -> Vec<EditOperation<T>> {
+    let max_d = max_d(ts1.len(), ts2.len());
+    let mut vf = Diagonals::new(max_d);
+    let mut vb = Diagonals::new(max_d);
+    let mut result: Vec<EditOperation<T>> = vec![];
+    conquer(
+        costs,
+        ts1,
+        (0, ts1.len()),
+        ts2,
+        (0, ts2.len()),
+        &mut vf,
+        &mut vb,
+        &mut result,
+    );
+    pair_substitutions(result)
+}
+
+// Furthest x coordinates reached on each diagonal k = x - y.
+// k can be negative so the values are stored with an offset
+struct Diagonals {
+    offset: isize,
+    values: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(max_d: usize) -> Diagonals {
+        Diagonals {
+            offset: max_d as isize,
+            values: vec![0; 2 * max_d + 1],
+        }
+    }
+
+    fn get(&self, k: isize) -> usize {
+        self.values[(k + self.offset) as usize]
+    }
+
+    fn set(&mut self, k: isize, x: usize) {
+        self.values[(k + self.offset) as usize] = x;
+    }
+}
+
+// Maximum number of steps needed to find the middle snake of 2 lists of tokens
+fn max_d(n: usize, m: usize) -> usize {
+    (n + m).div_ceil(2) + 1
+}
+
+// A range of tokens: (start, end)
+type Range = (usize, usize);
+
+// Diff 2 ranges of tokens by removing their common prefix and suffix, then by splitting them
+// around the middle snake of the edit graph and diffing each half recursively
+#[allow(clippy::too_many_arguments)]
+fn conquer<T: Clone>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    (mut start1, mut end1): Range,
+    ts2: &[T],
+    (mut start2, mut end2): Range,
+    vf: &mut Diagonals,
+    vb: &mut Diagonals,
+    result: &mut Vec<EditOperation<T>>,
+) {
+    let prefix = common_prefix(costs, &ts1[start1..end1], &ts2[start2..end2]);
+    result.extend(ts1[start1..start1 + prefix].iter().cloned().map(Keep));
+    start1 += prefix;
+    start2 += prefix;
+
+    let suffix = common_suffix(costs, &ts1[start1..end1], &ts2[start2..end2]);
+    let suffix_start = end1 - suffix;
+    end1 -= suffix;
+    end2 -= suffix;
+
+    if start1 == end1 {
+        result.extend(ts2[start2..end2].iter().cloned().map(Insert));
+    } else if start2 == end2 {
+        result.extend(ts1[start1..end1].iter().cloned().map(Delete));
+    } else {
+        let (x, y) = middle_snake(costs, ts1, (start1, end1), ts2, (start2, end2), vf, vb);
+        conquer(costs, ts1, (start1, x), ts2, (start2, y), vf, vb, result);
+        conquer(costs, ts1, (x, end1), ts2, (y, end2), vf, vb, result);
+    }
+
+    result.extend(
+        ts1[suffix_start..suffix_start + suffix]
+            .iter()
+            .cloned()
+            .map(Keep),
+    );
+}
+
+// Find a point of an optimal path in the edit graph by searching forward from the start
+// and backward from the end at the same time, until both searches overlap.
+// The ranges must not be empty and must not have a common prefix or suffix
+fn middle_snake<T>(
+    costs: &impl Costs<T>,
+    ts1: &[T],
+    (start1, end1): Range,
+    ts2: &[T],
+    (start2, end2): Range,
+    vf: &mut Diagonals,
+    vb: &mut Diagonals,
+) -> (usize, usize) {
+    let (n, m) = (end1 - start1, end2 - start2);
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    vf.set(1, 0);
+    vb.set(1, 0);
+
+    for d in 0..max_d(n, m) as isize {
+        // forward search
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && vf.get(k - 1) < vf.get(k + 1)) {
+                vf.get(k + 1)
+            } else {
+                vf.get(k - 1) + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix(costs, &ts1[start1 + x..end1], &ts2[start2 + y..end2]);
+            }
+            vf.set(k, x);
+            if odd && (k - delta).abs() < d && vf.get(k) + vb.get(delta - k) >= n {
+                return (start1 + x0, start2 + y0);
+            }
+        }
+        // backward search
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && vb.get(k - 1) < vb.get(k + 1)) {
+                vb.get(k + 1)
+            } else {
+                vb.get(k - 1) + 1
+            };
+            let y = (x as isize - k) as usize;
+            if x < n && y < m {
+                x += common_suffix(costs, &ts1[start1..end1 - x], &ts2[start2..end2 - y]);
+            }
+            vb.set(k, x);
+            if !odd && (k - delta).abs() <= d && vb.get(k) + vf.get(delta - k) >= n {
+                return (end1 - x, end2 - (x as isize - k) as usize);
+            }
+        }
+    }
+    // the searches always overlap before max_d steps
+    unreachable!("no middle snake found")
+}
+
+// Return the number of equal tokens at the start of 2 lists of tokens
+fn common_prefix<T>(costs: &impl Costs<T>, ts1: &[T], ts2: &[T]) -> usize {
+    ts1.iter()
+        .zip(ts2.iter())
+        .take_while(|(t1, t2)| costs.substitution_cost(t1, t2) == 0)
+        .count()
+}
+
+// Return the number of equal tokens at the end of 2 lists of tokens
+fn common_suffix<T>(costs: &impl Costs<T>, ts1: &[T], ts2: &[T]) -> usize {
+    ts1.iter()
+        .rev()
+        .zip(ts2.iter().rev())
+        .take_while(|(t1, t2)| costs.substitution_cost(t1, t2) == 0)
+        .count()
+}
+
+// Replace the deletions and insertions of each run of changes with substitutions.
+// The extra deletions or insertions are kept at the end of the run
+pub(crate) fn pair_substitutions<T>(operations: Vec<EditOperation<T>>) -> Vec<EditOperation<T>> {
+    let mut result: Vec<EditOperation<T>> = vec![];
+    let mut deleted: Vec<T> = vec![];
+    let mut inserted: Vec<T> = vec![];
+    for operation in operations {
+        match operation {
+            Delete(t) => deleted.push(t),
+            Insert(t) => inserted.push(t),
+            other => {
+                flush_changes(&mut deleted, &mut inserted, &mut result);
+                result.push(other);
+            }
+        }
+    }
+    flush_changes(&mut deleted, &mut inserted, &mut result);
+    result
+}
+
+// Add a run of deleted and inserted tokens to the list of operations
+fn flush_changes<T>(
+    deleted: &mut Vec<T>,
+    inserted: &mut Vec<T>,
+    result: &mut Vec<EditOperation<T>>,
+) {
+    let paired = deleted.len().min(inserted.len());
+    let mut deleted = deleted.drain(..);
+    let mut inserted = inserted.drain(..);
+    for (t1, t2) in deleted.by_ref().zip(inserted.by_ref()).take(paired) {
+        result.push(Substitute(t1, t2));
+    }
+    result.extend(deleted.map(Delete));
+    result.extend(inserted.map(Insert));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    // Return the length of the longest common subsequence of 2 lists of tokens
+    fn lcs(ts1: &[char], ts2: &[char]) -> usize {
+        let mut previous = vec![0; ts2.len() + 1];
+        for t1 in ts1 {
+            let mut current = vec![0; ts2.len() + 1];
+            for (j, t2) in ts2.iter().enumerate() {
+                current[j + 1] = if t1 == t2 {
+                    previous[j] + 1
+                } else {
+                    current[j].max(previous[j + 1])
+                };
+            }
+            previous = current;
+        }
+        previous[ts2.len()]
+    }
+
+    #[test]
+    fn test_myers_edit_operations() {
+        let costs = levenshtein_costs();
+        assert_eq!(
+            myers_edit_operations(&chars("kitten"), &chars("kittein"), &costs),
+            vec![
+                Keep('k'),
+                Keep('i'),
+                Keep('t'),
+                Keep('t'),
+                Keep('e'),
+                Insert('i'),
+                Keep('n')
+            ]
+        );
+        assert_eq!(
+            myers_edit_operations(&chars("abc"), &chars("adc"), &costs),
+            vec![Keep('a'), Substitute('b', 'd'), Keep('c')]
+        );
+        assert_eq!(
+            myers_edit_operations(&chars("abc"), &chars("xy"), &costs),
+            vec![Substitute('a', 'x'), Substitute('b', 'y'), Delete('c')]
+        );
+        assert_eq!(
+            myers_edit_operations(&chars(""), &chars("ab"), &costs),
+            vec![Insert('a'), Insert('b')]
+        );
+        assert_eq!(
+            myers_edit_operations(&chars(""), &chars(""), &costs),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_myers_edit_operations_with_comparison_costs() {
+        let mut costs = comparison_costs();
+        costs.ignore_case = true;
+        assert_eq!(
+            myers_edit_operations(&chars("aB"), &chars("Abc"), &costs),
+            vec![Keep('a'), Keep('B'), Insert('c')]
+        );
+    }
+
+    #[test]
+    fn test_myers_edit_operations_on_large_texts() {
+        // 2 texts of 1 million chars with a few differences
+        let text1: Vec<char> = "the quick brown fox jumps over the lazy dog "
+            .chars()
+            .cycle()
+            .take(1_000_000)
+            .collect();
+        let mut text2 = text1.clone();
+        text2[10] = '*';
+        text2.insert(500_000, '+');
+        text2.remove(900_000);
+        let operations = myers_edit_operations(&text1, &text2, &levenshtein_costs());
+        let changes = operations.iter().filter(|o| !matches!(o, Keep(_))).count();
+        assert_eq!(changes, 3);
+        assert_eq!(apply(&operations, &text1), Ok(text2));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_myers_edit_operations(s1 in "[a-d]{0,20}", s2 in "[a-d]{0,20}") {
+            let (ts1, ts2) = (chars(&s1), chars(&s2));
+            let operations = myers_edit_operations(&ts1, &ts2, &levenshtein_costs());
+            prop_assert_eq!(apply(&operations, &ts1), Ok(ts2.clone()));
+            // the kept tokens are a longest common subsequence
+            let kept = operations.iter().filter(|o| matches!(o, Keep(_))).count();
+            prop_assert_eq!(kept, lcs(&ts1, &ts2));
+        }
+    }
+}
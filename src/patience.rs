@@ -0,0 +1,336 @@
+use crate::costs::*;
+use crate::edit_operation::*;
+use crate::myers::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use EditOperation::*;
+
+// Return the list of operations necessary to go from one list of lines to another
+// with the patience diff algorithm.
+// The lines which appear exactly once in both texts are used as anchors, and the longest
+// list of anchors appearing in the same order in both texts is kept.
+// The regions between the anchors are then diffed recursively, and with the Myers algorithm
+// when they don't have any unique line.
+// This avoids aligning frequent lines, like lone braces, when the rest of the text is different
+pub fn patience_edit_operations<T: Clone + Eq + Hash>(
+    ts1: &[T],
+    ts2: &[T],
+) -> Vec<EditOperation<T>> {
+    diff_regions(ts1, ts2, patience_matches)
+}
+
+// Return the list of operations necessary to go from one list of lines to another
+// with the histogram diff algorithm.
+// This extends the patience algorithm by using the common lines which are the least frequent
+// in the first text as anchors, even if they are not unique.
+// The longest region of common lines around such a line is kept and the regions
+// before and after it are diffed recursively
+pub fn histogram_edit_operations<T: Clone + Eq + Hash>(
+    ts1: &[T],
+    ts2: &[T],
+) -> Vec<EditOperation<T>> {
+    diff_regions(ts1, ts2, histogram_matches)
+}
+
+// Lines appearing more often than this in a region are not used as anchors
+const MAX_OCCURRENCES: usize = 64;
+
+// A range of lines: (start, end)
+type Range = (usize, usize);
+
+// A region of equal lines: start in the first text, start in the second text, length
+type Match = (usize, usize, usize);
+
+// Function returning the regions of equal lines, in order, to keep between 2 ranges of lines
+type FindMatches<T> = fn(&[T], Range, &[T], Range) -> Vec<Match>;
+
+// Diff regions of lines which remain to be diffed, or lines which must be kept
+enum Task {
+    Diff(Range, Range),
+    Keep(Range),
+}
+
+// Diff 2 lists of lines by finding matches, then by diffing the regions between them.
+// Regions are processed with a stack instead of recursive calls to support large texts
+fn diff_regions<T: Clone + Eq + Hash>(
+    ts1: &[T],
+    ts2: &[T],
+    find_matches: FindMatches<T>,
+) -> Vec<EditOperation<T>> {
+    let mut result: Vec<EditOperation<T>> = vec![];
+    let mut tasks = vec![Task::Diff((0, ts1.len()), (0, ts2.len()))];
+    while let Some(task) = tasks.pop() {
+        let ((mut start1, mut end1), (mut start2, mut end2)) = match task {
+            Task::Keep((start, end)) => {
+                result.extend(ts1[start..end].iter().cloned().map(Keep));
+                continue;
+            }
+            Task::Diff(range1, range2) => (range1, range2),
+        };
+
+        let prefix = common_length(ts1[start1..end1].iter(), ts2[start2..end2].iter());
+        result.extend(ts1[start1..start1 + prefix].iter().cloned().map(Keep));
+        start1 += prefix;
+        start2 += prefix;
+        let suffix = common_length(
+            ts1[start1..end1].iter().rev(),
+            ts2[start2..end2].iter().rev(),
+        );
+        tasks.push(Task::Keep((end1 - suffix, end1)));
+        end1 -= suffix;
+        end2 -= suffix;
+
+        if start1 == end1 {
+            result.extend(ts2[start2..end2].iter().cloned().map(Insert));
+        } else if start2 == end2 {
+            result.extend(ts1[start1..end1].iter().cloned().map(Delete));
+        } else {
+            let matches = find_matches(ts1, (start1, end1), ts2, (start2, end2));
+            if matches.is_empty() {
+                result.extend(myers_edit_operations(
+                    &ts1[start1..end1],
+                    &ts2[start2..end2],
+                    &levenshtein_costs(),
+                ));
+            } else {
+                // the tasks are pushed in reverse order since the last pushed task is processed first
+                let (mut next1, mut next2) = (end1, end2);
+                for (i, j, length) in matches.into_iter().rev() {
+                    tasks.push(Task::Diff((i + length, next1), (j + length, next2)));
+                    tasks.push(Task::Keep((i, i + length)));
+                    (next1, next2) = (i, j);
+                }
+                tasks.push(Task::Diff((start1, next1), (start2, next2)));
+            }
+        }
+    }
+    pair_substitutions(result)
+}
+
+// Return the number of equal lines at the start of 2 lists of lines
+fn common_length<'a, T: Eq + 'a>(
+    ts1: impl Iterator<Item = &'a T>,
+    ts2: impl Iterator<Item = &'a T>,
+) -> usize {
+    ts1.zip(ts2).take_while(|(t1, t2)| t1 == t2).count()
+}
+
+// Return the lines appearing exactly once in both ranges, which form the longest
+// sequence of lines appearing in the same order in both ranges
+fn patience_matches<T: Eq + Hash>(
+    ts1: &[T],
+    (start1, end1): Range,
+    ts2: &[T],
+    (start2, end2): Range,
+) -> Vec<Match> {
+    // for each line: number of occurrences and position in each range
+    let mut occurrences: HashMap<&T, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, t) in ts1.iter().enumerate().take(end1).skip(start1) {
+        let entry = occurrences.entry(t).or_insert((0, i, 0, 0));
+        entry.0 += 1;
+    }
+    for (j, t) in ts2.iter().enumerate().take(end2).skip(start2) {
+        if let Some(entry) = occurrences.get_mut(t) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+    let mut unique: Vec<(usize, usize)> = occurrences
+        .into_values()
+        .filter(|(count1, _, count2, _)| *count1 == 1 && *count2 == 1)
+        .map(|(_, i, _, j)| (i, j))
+        .collect();
+    unique.sort();
+
+    longest_increasing_sequence(&unique)
+        .into_iter()
+        .map(|(i, j)| (i, j, 1))
+        .collect()
+}
+
+// Return the longest sequence of pairs, sorted by their first element,
+// where the second elements are also increasing (patience sorting)
+fn longest_increasing_sequence(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // index of the last pair of each pile and index of the previous pair for each pair
+    let mut piles: Vec<usize> = vec![];
+    let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, (_, j)) in pairs.iter().enumerate() {
+        let pile = piles.partition_point(|p| pairs[*p].1 < *j);
+        previous[index] = pile.checked_sub(1).map(|p| piles[p]);
+        if pile == piles.len() {
+            piles.push(index);
+        } else {
+            piles[pile] = index;
+        }
+    }
+
+    let mut result = vec![];
+    let mut current = piles.last().copied();
+    while let Some(index) = current {
+        result.push(pairs[index]);
+        current = previous[index];
+    }
+    result.reverse();
+    result
+}
+
+// Return the longest region of equal lines around the common line which has the lowest
+// number of occurrences in the first range
+fn histogram_matches<T: Eq + Hash>(
+    ts1: &[T],
+    (start1, end1): Range,
+    ts2: &[T],
+    (start2, end2): Range,
+) -> Vec<Match> {
+    let mut positions: HashMap<&T, Vec<usize>> = HashMap::new();
+    for (i, t) in ts1.iter().enumerate().take(end1).skip(start1) {
+        positions.entry(t).or_default().push(i);
+    }
+
+    // best region found so far with its lowest number of occurrences
+    let mut best: Option<(Match, usize)> = None;
+    let mut j = start2;
+    while j < end2 {
+        let mut next = j + 1;
+        let occurrences = positions
+            .get(&ts2[j])
+            .filter(|is| is.len() <= MAX_OCCURRENCES);
+        for i in occurrences.into_iter().flatten() {
+            // extend the region around the common line
+            let before = common_length(ts1[start1..*i].iter().rev(), ts2[start2..j].iter().rev());
+            let after = common_length(ts1[*i..end1].iter(), ts2[j..end2].iter());
+            let (region1, region2, length) = (i - before, j - before, before + after);
+            let count = ts1[region1..region1 + length]
+                .iter()
+                .map(|t| positions.get(t).map(|is| is.len()).unwrap_or(0))
+                .min()
+                .unwrap_or(0);
+            let is_better = match best {
+                None => true,
+                Some(((_, _, best_length), best_count)) => {
+                    count < best_count || (count == best_count && length > best_length)
+                }
+            };
+            if is_better {
+                best = Some(((region1, region2, length), count));
+            }
+            next = next.max(region2 + length);
+        }
+        j = next;
+    }
+    best.map(|(region, _)| vec![region]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.split('\n').map(|l| l.to_string()).collect()
+    }
+
+    // Show the operations on lines like a unified diff
+    fn show(operations: Vec<EditOperation<String>>) -> String {
+        operations
+            .into_iter()
+            .map(|o| match o {
+                Keep(l) => format!(" {l}"),
+                Insert(l) => format!("+{l}"),
+                Delete(l) => format!("-{l}"),
+                Substitute(l1, l2) => format!("-{l1}\n+{l2}"),
+                Transpose(l1, l2) => format!("-{l1}\n+{l2}"),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    const BEFORE: &str = "void f()
+{
+    one();
+}
+
+void g()
+{
+    two();
+}";
+
+    const AFTER: &str = "void g()
+{
+    two();
+}
+
+void h()
+{
+    three();
+}";
+
+    #[test]
+    fn test_patience_edit_operations() {
+        // the unique lines are kept and the braces are not aligned on their own.
+        // The common last line is kept at the end
+        assert_eq!(
+            show(patience_edit_operations(&lines(BEFORE), &lines(AFTER))),
+            "-void f()
+-{
+-    one();
+-}
+-
+ void g()
+ {
+     two();
++}
++
++void h()
++{
++    three();
+ }"
+        );
+    }
+
+    #[test]
+    fn test_histogram_edit_operations() {
+        assert_eq!(
+            show(histogram_edit_operations(&lines(BEFORE), &lines(AFTER))),
+            show(patience_edit_operations(&lines(BEFORE), &lines(AFTER)))
+        );
+        // "b" is not unique but it is used as an anchor because it is the least frequent line
+        assert_eq!(
+            histogram_edit_operations(&lines("a\nb\nb\nc\nc\nc"), &lines("b\nx\nb\nc")),
+            vec![
+                Delete("a".to_string()),
+                Keep("b".to_string()),
+                Insert("x".to_string()),
+                Keep("b".to_string()),
+                Delete("c".to_string()),
+                Delete("c".to_string()),
+                Keep("c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_longest_increasing_sequence() {
+        assert_eq!(
+            longest_increasing_sequence(&[(0, 3), (1, 1), (2, 4), (3, 2), (4, 5)]),
+            vec![(1, 1), (3, 2), (4, 5)]
+        );
+        assert_eq!(longest_increasing_sequence(&[]), vec![]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_line_edit_operations(s1 in "[a-c\n]{0,20}", s2 in "[a-c\n]{0,20}") {
+            let (ls1, ls2) = (lines(&s1), lines(&s2));
+            prop_assert_eq!(apply(&patience_edit_operations(&ls1, &ls2), &ls1), Ok(ls2.clone()));
+            prop_assert_eq!(apply(&histogram_edit_operations(&ls1, &ls2), &ls1), Ok(ls2.clone()));
+        }
+        #[test]
+        fn prop_line_edit_operations_identical(s in "[a-c\n]{0,20}") {
+            let ls = lines(&s);
+            let kept: Vec<EditOperation<String>> = ls.iter().cloned().map(Keep).collect();
+            prop_assert_eq!(patience_edit_operations(&ls, &ls), kept.clone());
+            prop_assert_eq!(histogram_edit_operations(&ls, &ls), kept);
+        }
+    }
+}
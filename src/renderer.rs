@@ -0,0 +1,300 @@
+use crate::color::*;
+use crate::difference::*;
+use crate::edit_operation::*;
+use std::fmt::Debug;
+use Color::*;
+use EditOperation::*;
+
+// A DiffRenderer specifies how the differences between 2 texts are displayed:
+//  - start_difference / end_difference: text displayed around a region of consecutive differences
+//  - render_operation: text displayed for each edit operation, including the kept tokens
+//  - render_run: text displayed for consecutive differences of the same kind. By default each operation is rendered
+//  - render_elided: text displayed in place of the tokens removed when shortening the output
+//
+// The methods take &self so that a renderer can be shared between several display options
+// and can keep some state, like a theme or a counter, with interior mutability
+pub trait DiffRenderer: Debug + Send + Sync {
+    fn start_difference(&self) -> String;
+    fn end_difference(&self) -> String;
+    fn render_operation(&self, operation: EditOperation<String>) -> String;
+    fn render_run(&self, operations: Vec<EditOperation<String>>) -> String {
+        operations
+            .into_iter()
+            .map(|operation| self.render_operation(operation))
+            .collect()
+    }
+    fn render_elided(&self, text: String) -> String {
+        text
+    }
+}
+
+// Render the differences inside separators, with a symbol showing which operation is used:
+// kit[~t/s~e/i]n
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BracketsRenderer {
+    pub separators: Separators,
+}
+
+pub fn brackets_renderer() -> BracketsRenderer {
+    BracketsRenderer {
+        separators: brackets_separators(),
+    }
+}
+
+impl DiffRenderer for BracketsRenderer {
+    fn start_difference(&self) -> String {
+        self.separators.start_separator.clone()
+    }
+
+    fn end_difference(&self) -> String {
+        self.separators.end_separator.clone()
+    }
+
+    fn render_operation(&self, operation: EditOperation<String>) -> String {
+        match operation {
+            Insert(s) => format!("+{}", s),
+            Delete(s) => format!("-{}", s),
+            Substitute(s1, s2) => format!("~{}/{}", s1, s2),
+            Transpose(s1, s2) => format!("{}<>{}", s1, s2),
+            Keep(s) => s,
+        }
+    }
+}
+
+// Render the differences inside separators, with a terminal style for each kind of operation.
+// By default, like other diff tools: inserted = green underline, deleted = red strikethrough,
+// substituted = cyan, transposed = magenta
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ColoredRenderer {
+    pub separators: Separators,
+    pub inserted: Style,
+    pub deleted: Style,
+    pub substituted: Style,
+    pub transposed: Style,
+}
+
+pub fn colored_renderer() -> ColoredRenderer {
+    ColoredRenderer {
+        separators: brackets_separators(),
+        inserted: Style {
+            underline: true,
+            ..color_style(Green)
+        },
+        deleted: Style {
+            strikethrough: true,
+            ..color_style(Red)
+        },
+        substituted: color_style(Cyan),
+        transposed: color_style(Magenta),
+    }
+}
+
+impl DiffRenderer for ColoredRenderer {
+    fn start_difference(&self) -> String {
+        self.separators.start_separator.clone()
+    }
+
+    fn end_difference(&self) -> String {
+        self.separators.end_separator.clone()
+    }
+
+    fn render_operation(&self, operation: EditOperation<String>) -> String {
+        match operation {
+            Insert(s) => s.style_as(self.inserted),
+            Delete(s) => s.style_as(self.deleted),
+            Substitute(s, _) => s.style_as(self.substituted),
+            Transpose(s1, s2) => format!("{}{}", s1, s2).style_as(self.transposed),
+            Keep(s) => s,
+        }
+    }
+
+    // Style a run of operations only once, instead of styling each token
+    fn render_run(&self, operations: Vec<EditOperation<String>>) -> String {
+        let style = match operations.first() {
+            Some(Insert(_)) => self.inserted,
+            Some(Delete(_)) => self.deleted,
+            Some(Substitute(_, _)) => self.substituted,
+            Some(Transpose(_, _)) => self.transposed,
+            Some(Keep(_)) | None => plain_style(),
+        };
+        let text: String = operations
+            .into_iter()
+            .map(|operation| match operation {
+                Insert(s) | Delete(s) | Substitute(s, _) | Keep(s) => s,
+                Transpose(s1, s2) => format!("{}{}", s1, s2),
+            })
+            .collect();
+        text.style_as(style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn s(s: &str) -> String {
+        s.to_string()
+    }
+
+    #[test]
+    fn test_brackets_renderer() {
+        let renderer = brackets_renderer();
+        assert_eq!(renderer.start_difference(), "[");
+        assert_eq!(renderer.end_difference(), "]");
+        assert_eq!(renderer.render_operation(Insert(s("a"))), "+a");
+        assert_eq!(renderer.render_operation(Delete(s("a"))), "-a");
+        assert_eq!(
+            renderer.render_operation(Substitute(s("a"), s("b"))),
+            "~a/b"
+        );
+        assert_eq!(renderer.render_operation(Transpose(s("a"), s("b"))), "a<>b");
+        assert_eq!(renderer.render_operation(Keep(s("a"))), "a");
+        assert_eq!(renderer.render_elided(s("...")), "...");
+    }
+
+    #[test]
+    fn test_colored_renderer() {
+        let renderer = colored_renderer();
+        assert_eq!(
+            renderer.render_operation(Insert(s("a"))),
+            "\x1b[4;32ma\x1b[0m"
+        );
+        assert_eq!(
+            renderer.render_operation(Delete(s("a"))),
+            "\x1b[9;31ma\x1b[0m"
+        );
+        assert_eq!(
+            renderer.render_operation(Transpose(s("a"), s("b"))),
+            "\x1b[35mab\x1b[0m"
+        );
+        assert_eq!(renderer.render_operation(Keep(s("a"))), "a");
+
+        // the styles can be changed
+        let renderer = ColoredRenderer {
+            inserted: color_style(Rgb(0, 255, 0)),
+            ..colored_renderer()
+        };
+        assert_eq!(
+            renderer.render_operation(Insert(s("a"))),
+            "\x1b[38;2;0;255;0ma\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_colored_renderer_run() {
+        let renderer = colored_renderer();
+        assert_eq!(
+            renderer.render_run(vec![Insert(s("a")), Insert(s("b"))]),
+            "\x1b[4;32mab\x1b[0m"
+        );
+        assert_eq!(
+            renderer.render_run(vec![Transpose(s("a"), s("b")), Transpose(s("c"), s("d"))]),
+            "\x1b[35mabcd\x1b[0m"
+        );
+        // the brackets renderer displays each operation
+        assert_eq!(
+            brackets_renderer().render_run(vec![Insert(s("a")), Insert(s("b"))]),
+            "+a+b"
+        );
+    }
+
+    // A renderer numbering each region of differences
+    #[derive(Debug, Default)]
+    struct NumberedRenderer {
+        count: AtomicUsize,
+    }
+
+    impl DiffRenderer for NumberedRenderer {
+        fn start_difference(&self) -> String {
+            format!("<{}:", self.count.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn end_difference(&self) -> String {
+            ">".to_string()
+        }
+
+        fn render_operation(&self, operation: EditOperation<String>) -> String {
+            brackets_renderer().render_operation(operation)
+        }
+
+        fn render_elided(&self, _text: String) -> String {
+            "…".to_string()
+        }
+    }
+
+    #[test]
+    fn test_custom_renderer() {
+        let mut options = default_display_options();
+        options.renderer = Arc::new(NumberedRenderer::default());
+        let operations = vec![
+            Insert(s("a")),
+            Keep(s("b")),
+            Delete(s("c")),
+            Substitute(s("d"), s("e")),
+        ];
+        assert_eq!(display_diffs(options, operations), "<1:+a>b<2:-c~d/e>");
+
+        let mut options = default_display_options();
+        options.renderer = Arc::new(NumberedRenderer::default());
+        options.shorten_options.size = 2;
+        let mut operations: Vec<EditOperation<String>> =
+            "abcde".chars().map(|c| Keep(c.to_string())).collect();
+        operations.push(Insert(s("f")));
+        assert_eq!(display_diffs(options, operations), "…de<1:+f>");
+    }
+
+    // A renderer counting the operations and the elided texts which are rendered
+    #[derive(Debug, Default)]
+    struct CountingRenderer {
+        operations: AtomicUsize,
+        elided: AtomicUsize,
+    }
+
+    impl DiffRenderer for CountingRenderer {
+        fn start_difference(&self) -> String {
+            "[".to_string()
+        }
+
+        fn end_difference(&self) -> String {
+            "]".to_string()
+        }
+
+        fn render_operation(&self, operation: EditOperation<String>) -> String {
+            self.operations.fetch_add(1, Ordering::Relaxed);
+            brackets_renderer().render_operation(operation)
+        }
+
+        fn render_elided(&self, text: String) -> String {
+            self.elided.fetch_add(1, Ordering::Relaxed);
+            text
+        }
+    }
+
+    #[test]
+    fn test_renderer_only_renders_displayed_text() {
+        let count = |operations: Vec<EditOperation<String>>| {
+            let renderer = Arc::new(CountingRenderer::default());
+            let mut options = default_display_options();
+            options.renderer = renderer.clone();
+            options.shorten_options.size = 2;
+            let displayed = display_diffs(options, operations);
+            (
+                displayed,
+                renderer.operations.load(Ordering::Relaxed),
+                renderer.elided.load(Ordering::Relaxed),
+            )
+        };
+        let mut operations: Vec<EditOperation<String>> =
+            "abcde".chars().map(|c| Keep(c.to_string())).collect();
+        operations.push(Insert(s("f")));
+        // the elided tokens a, b, c are not rendered
+        assert_eq!(count(operations), (s("...de[+f]"), 3, 1));
+        // nothing is elided
+        assert_eq!(
+            count(vec![Keep(s("a")), Insert(s("b"))]),
+            (s("a[+b]"), 2, 0)
+        );
+    }
+}
@@ -69,16 +69,15 @@ fn split_on_delimiters(start: Token, end: Token, tokens: Vec<Token>) -> Vec<Vec<
 }
 
 fn update_last<T: Clone>(result: &mut [Vec<T>], t: T) {
-    match result.last_mut() {
-        Some(l) => l.push(t),
-        None => (),
+    if let Some(l) = result.last_mut() {
+        l.push(t)
     };
 }
 
 // Shorten some token on the left: ...tokens
 fn shorten_left(so: ShortenOptions, original: Vec<Token>) -> Vec<Token> {
     if token_size(&original) > so.size.into() {
-        let mut shortened = vec![Kept(so.text)];
+        let mut shortened = vec![Elided(so.text)];
         shortened.extend(
             original
                 .iter()
@@ -96,7 +95,7 @@ fn shorten_right(so: ShortenOptions, original: Vec<Token>) -> Vec<Token> {
     if token_size(&original) > so.size.into() {
         let mut shortened = vec![];
         shortened.extend(original.iter().take(so.size.into()).cloned());
-        shortened.push(Kept(so.text));
+        shortened.push(Elided(so.text));
         shortened
     } else {
         original.to_vec()
@@ -109,7 +108,7 @@ fn shorten_center(so: ShortenOptions, original: Vec<Token>) -> Vec<Token> {
         let half_size = half(&so).size.into();
         let mut shortened = vec![];
         shortened.extend(original.iter().take(half_size).cloned());
-        shortened.push(Kept(so.text));
+        shortened.push(Elided(so.text));
         shortened.extend(original.iter().skip(original.len() - half_size).cloned());
         shortened
     } else {
@@ -118,15 +117,11 @@ fn shorten_center(so: ShortenOptions, original: Vec<Token>) -> Vec<Token> {
 }
 
 // Return the size of a list of tokens by only considering
-// the strings we want to kept
+// the strings we want to kept.
+// Each kept string is a displayed unit of text (a char, a grapheme,...) and counts for 1
+// so that the size is consistent with the number of tokens which are skipped or taken when shortening
 fn token_size(ts: &[Token]) -> usize {
-    return ts
-        .iter()
-        .map(|t| match t {
-            Kept(s) => s.len(),
-            _ => 0,
-        })
-        .sum();
+    ts.iter().filter(|t| matches!(t, Kept(_) | Item(_))).count()
 }
 
 // TESTS
@@ -191,6 +186,52 @@ mod tests {
         assert_eq!(split_on_delimiters(start, end, delimited), expected);
     }
     #[test]
+    fn test_shorten_unicode() {
+        // multi-bytes characters count as 1
+        assert_eq!(shorten("日本語"), "日本語");
+        assert_eq!(shorten("éééééééé[mn]"), "...ééééé[mn]");
+        let so = ShortenOptions {
+            size: 2,
+            text: "...".to_string(),
+        };
+        // a grapheme made of several chars counts as 1
+        let tokens = vec![kept("e\u{301}"), kept("e\u{301}")];
+        assert_eq!(token_size(&tokens), 2);
+        assert_eq!(
+            show_tokens(shorten_left(so, tokens.clone())),
+            show_tokens(tokens)
+        );
+    }
+    #[test]
+    fn test_shorten_items() {
+        // items count like kept strings, delimiters of differences are left in place
+        let tokens = vec![
+            Item(0),
+            Item(1),
+            Item(2),
+            DifferenceStart,
+            Item(3),
+            DifferenceEnd,
+        ];
+        assert_eq!(token_size(&tokens), 4);
+        let so = ShortenOptions {
+            size: 2,
+            text: "...".to_string(),
+        };
+        assert_eq!(
+            shorten_tokens(so, DifferenceStart, DifferenceEnd, tokens),
+            vec![
+                Elided("...".to_string()),
+                Item(1),
+                Item(2),
+                DifferenceStart,
+                Item(3),
+                DifferenceEnd,
+                End
+            ]
+        );
+    }
+    #[test]
     fn test_shorten() {
         assert_eq!(shorten("abcd"), "abcd");
         assert_eq!(shorten("abcdefghijkl[mn]opqr"), "...hijkl[mn]opqr");
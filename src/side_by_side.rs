@@ -0,0 +1,312 @@
+use crate::difference::*;
+use crate::edit_operation::*;
+use terminal_size::{terminal_size, Width};
+use unicode_width::UnicodeWidthChar;
+use EditOperation::*;
+
+// Options for displaying differences in 2 columns
+//  - width: total width of the 2 columns and of the gutter between them.
+//    When it is not set, the width of the terminal is used
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SideBySideOptions {
+    pub width: Option<usize>,
+}
+
+pub fn default_side_by_side_options() -> SideBySideOptions {
+    SideBySideOptions { width: None }
+}
+
+// Return the width of the terminal, or the COLUMNS variable, or 80 if they are not available
+pub fn terminal_width() -> usize {
+    match terminal_size() {
+        Some((Width(width), _)) => width.into(),
+        None => std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80),
+    }
+}
+
+// Show the differences line by line with the first text on the left and the second text on the right.
+// A marker is displayed between the 2 columns, like `diff -y`:
+//  - ' ': the line is kept
+//  - '|': the line is modified, the differences are shown on both sides, with the display options
+//  - '<': the line is deleted
+//  - '>': the line is inserted
+// Lines which are too long for their column are wrapped on several rows and tabs are expanded to spaces.
+// The perspective and the line options of the display options are not used: both texts are always displayed,
+// and the markers already show how each line is edited
+pub fn display_side_by_side(
+    options: DisplayOptions,
+    side_by_side_options: SideBySideOptions,
+    line_operations: Vec<(EditOperation<String>, Vec<EditOperation<String>>)>,
+) -> String {
+    let width = side_by_side_options.width.unwrap_or_else(terminal_width);
+    let column = (width.saturating_sub(3) / 2).max(1);
+    let display =
+        |operations: Vec<EditOperation<String>>| display_diffs(options.clone(), operations);
+    let mut rows: Vec<(String, char, String)> = vec![];
+
+    for (line_operation, operations) in line_operations {
+        let row = match line_operation {
+            Keep(_) => (
+                display(left_side(&operations)),
+                ' ',
+                display(right_side(&operations)),
+            ),
+            Delete(_) => (display(kept(operations)), '<', String::new()),
+            Insert(_) => (String::new(), '>', display(kept(operations))),
+            Substitute(_, _) | Transpose(_, _) => (
+                display(left_side(&operations)),
+                '|',
+                display(right_side(&operations)),
+            ),
+        };
+        rows.push(row);
+    }
+    display_columns(rows, column)
+}
+
+// Display rows made of a left text, a marker and a right text, in 2 columns of a given width.
+// Texts which are too long for their column are wrapped on several rows
+pub(crate) fn display_columns(rows: Vec<(String, char, String)>, column: usize) -> String {
+    let mut result: Vec<String> = vec![];
+    for (left, marker, right) in rows {
+        let lefts = wrap(&expand_tabs(&left), column);
+        let rights = wrap(&expand_tabs(&right), column);
+        for i in 0..lefts.len().max(rights.len()) {
+            let left = lefts.get(i).map(|l| l.as_str()).unwrap_or("");
+            let row = match rights.get(i) {
+                Some(right) if !right.is_empty() => {
+                    format!("{}{} {} {}", left, padding(left, column), marker, right)
+                }
+                _ => format!("{}{} {}", left, padding(left, column), marker)
+                    .trim_end()
+                    .to_string(),
+            };
+            result.push(row);
+        }
+    }
+    result.join("\n")
+}
+
+// Return the operations which apply to the tokens of the first text
+fn left_side(operations: &[EditOperation<String>]) -> Vec<EditOperation<String>> {
+    let mut result: Vec<EditOperation<String>> = vec![];
+    for operation in operations {
+        match operation {
+            Keep(t) => result.push(Keep(t.clone())),
+            Delete(t) | Substitute(t, _) => result.push(Delete(t.clone())),
+            Transpose(t1, t2) => result.extend([Delete(t1.clone()), Delete(t2.clone())]),
+            Insert(_) => (),
+        }
+    }
+    result
+}
+
+// Return the operations which apply to the tokens of the second text
+fn right_side(operations: &[EditOperation<String>]) -> Vec<EditOperation<String>> {
+    let mut result: Vec<EditOperation<String>> = vec![];
+    for operation in operations {
+        match operation {
+            Keep(t) => result.push(Keep(t.clone())),
+            Insert(t) | Substitute(_, t) => result.push(Insert(t.clone())),
+            Transpose(t1, t2) => result.extend([Insert(t2.clone()), Insert(t1.clone())]),
+            Delete(_) => (),
+        }
+    }
+    result
+}
+
+// Keep all the tokens of a line which is inserted or deleted as a whole,
+// since the marker already shows the difference
+fn kept(operations: Vec<EditOperation<String>>) -> Vec<EditOperation<String>> {
+    operations
+        .into_iter()
+        .map(|operation| match operation {
+            Insert(t) | Delete(t) | Keep(t) => Keep(t),
+            Substitute(_, t) => Keep(t),
+            Transpose(t1, t2) => Keep(format!("{}{}", t1, t2)),
+        })
+        .collect()
+}
+
+// Split a piece of text into rows which are not wider than the column.
+// ANSI escape sequences don't take any space and a color is closed at the end
+// of a row then opened again on the next row
+fn wrap(s: &str, column: usize) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    let mut row = String::new();
+    let mut row_width = 0;
+    let mut color: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            let mut escape = c.to_string();
+            for e in chars.by_ref() {
+                escape.push(e);
+                if e == 'm' {
+                    break;
+                }
+            }
+            color = if escape == "\x1b[0m" {
+                None
+            } else {
+                Some(escape.clone())
+            };
+            row.push_str(&escape);
+            continue;
+        }
+        let width = c.width().unwrap_or(0);
+        if row_width + width > column && row_width > 0 {
+            if color.is_some() {
+                row.push_str("\x1b[0m");
+            }
+            result.push(row);
+            row = color.clone().unwrap_or_default();
+            row_width = 0;
+        }
+        row.push(c);
+        row_width += width;
+    }
+    result.push(row);
+    result
+}
+
+// Number of columns between 2 tab stops
+const TAB_WIDTH: usize = 8;
+
+// Replace each tab with the spaces needed to reach the next tab stop, every 8 columns,
+// since a tab has no width of its own to wrap and pad the text
+fn expand_tabs(s: &str) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in s.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            in_escape = c != 'm';
+        } else if c == '\t' {
+            let spaces = TAB_WIDTH - width % TAB_WIDTH;
+            result.push_str(&" ".repeat(spaces));
+            width += spaces;
+            continue;
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+        result.push(c);
+    }
+    result
+}
+
+// Return the spaces needed to fill a column after a piece of text
+fn padding(s: &str, column: usize) -> String {
+    " ".repeat(column.saturating_sub(visible_width(s)))
+}
+
+// Return the width of a piece of text in a terminal, without the ANSI escape sequences
+pub(crate) fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in s.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            in_escape = c != 'm';
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(s: &str) -> String {
+        s.to_string()
+    }
+
+    fn line_operations() -> Vec<(EditOperation<String>, Vec<EditOperation<String>>)> {
+        vec![
+            (Keep(s("ab")), vec![Keep(s("a")), Keep(s("b"))]),
+            (
+                Substitute(s("cd"), s("ce")),
+                vec![Keep(s("c")), Substitute(s("d"), s("e"))],
+            ),
+            (Delete(s("f")), vec![Delete(s("f"))]),
+            (Insert(s("gh")), vec![Insert(s("g")), Insert(s("h"))]),
+        ]
+    }
+
+    #[test]
+    fn test_display_side_by_side() {
+        let options = SideBySideOptions { width: Some(23) };
+        assert_eq!(
+            display_side_by_side(default_display_options(), options, line_operations()),
+            "ab           ab\nc[-d]      | c[+e]\nf          <\n           > gh"
+        );
+    }
+
+    #[test]
+    fn test_display_side_by_side_long_lines() {
+        let long = |c: &str| s(&"a".repeat(30)) + c + &"b".repeat(30);
+        let keep = |t: String| t.chars().map(|c| Keep(c.to_string())).collect::<Vec<_>>();
+        let mut operations = keep("a".repeat(30));
+        operations.push(Substitute(s("x"), s("y")));
+        operations.extend(keep("b".repeat(30)));
+        let line_operations = vec![(Substitute(long("x"), long("y")), operations)];
+
+        // the kept text around the differences is shortened
+        let options = SideBySideOptions { width: Some(103) };
+        let a = "a".repeat(20);
+        let b = "b".repeat(20);
+        assert_eq!(
+            display_side_by_side(default_display_options(), options, line_operations.clone()),
+            format!("...{a}[-x]{b}... | ...{a}[+y]{b}...")
+        );
+        // lines which are still too long are wrapped
+        let options = SideBySideOptions { width: Some(23) };
+        assert_eq!(
+            display_side_by_side(default_display_options(), options, line_operations),
+            [
+                "...aaaaaaa | ...aaaaaaa",
+                "aaaaaaaaaa | aaaaaaaaaa",
+                "aaa[-x]bbb | aaa[+y]bbb",
+                "bbbbbbbbbb | bbbbbbbbbb",
+                "bbbbbbb... | bbbbbbb..."
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("abcde", 2), vec!["ab", "cd", "e"]);
+        assert_eq!(wrap("", 2), vec![""]);
+        // wide chars are not split across rows
+        assert_eq!(wrap("日本語", 5), vec!["日本", "語"]);
+        // colors are closed and opened again
+        assert_eq!(
+            wrap("\x1b[31mabc\x1b[0md", 2),
+            vec!["\x1b[31mab\x1b[0m", "\x1b[31mc\x1b[0md"]
+        );
+        assert_eq!(visible_width("\x1b[31m日本\x1b[0m"), 4);
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("a\tb"), "a       b");
+        assert_eq!(expand_tabs("\t"), " ".repeat(8));
+        // escape sequences don't take any space
+        assert_eq!(
+            expand_tabs("\x1b[31ma\x1b[0m\tb"),
+            "\x1b[31ma\x1b[0m       b"
+        );
+        // rows with tabs are aligned
+        let rows = vec![(s("a\tb"), '|', s("c")), (s("abcdefghij"), '|', s("d"))];
+        assert_eq!(display_columns(rows, 10), "a       b  | c\nabcdefghij | d");
+    }
+}
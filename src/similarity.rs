@@ -0,0 +1,286 @@
+use crate::costs::*;
+use crate::distance::*;
+use crate::edit_matrix::*;
+use crate::matrix::*;
+
+// This component returns a score to rank how close 2 strings are.
+// All the metrics are computed on chars
+pub trait Metric: Copy {
+    // Return a score between 0.0 (completely different) and 1.0 (identical)
+    fn similarity(self, s1: &str, s2: &str) -> f64;
+    // Return a score between 0.0 (identical) and 1.0 (completely different)
+    fn normalized_distance(self, s1: &str, s2: &str) -> f64 {
+        1.0 - self.similarity(s1, s2)
+    }
+}
+
+// Metric based on the Levenshtein distance, normalized by the length of the longest string
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LevenshteinMetric {}
+
+impl Metric for LevenshteinMetric {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        normalize(levenshtein_distance(s1, s2), s1, s2)
+    }
+}
+
+pub fn levenshtein_metric() -> LevenshteinMetric {
+    LevenshteinMetric {}
+}
+
+// Metric based on the Damerau distance, normalized by the length of the longest string
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct DamerauMetric {}
+
+impl Metric for DamerauMetric {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        normalize(damerau_distance(s1, s2), s1, s2)
+    }
+}
+
+pub fn damerau_metric() -> DamerauMetric {
+    DamerauMetric {}
+}
+
+// Metric based on the Hamming distance, normalized by the length of the longest string.
+// When the strings don't have the same length, the extra chars are counted as differences
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct HammingMetric {}
+
+impl Metric for HammingMetric {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        let (n1, n2) = (s1.chars().count(), s2.chars().count());
+        let differences = s1
+            .chars()
+            .zip(s2.chars())
+            .filter(|(c1, c2)| c1 != c2)
+            .count();
+        normalize(differences + n1.abs_diff(n2), s1, s2)
+    }
+}
+
+pub fn hamming_metric() -> HammingMetric {
+    HammingMetric {}
+}
+
+// Jaro similarity: proportion of matching chars, which are chars close enough to each other,
+// adjusted by the number of matching chars which are not in the same order
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct JaroMetric {}
+
+impl Metric for JaroMetric {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        jaro_similarity(s1, s2)
+    }
+}
+
+pub fn jaro_metric() -> JaroMetric {
+    JaroMetric {}
+}
+
+// Jaro-Winkler similarity: the Jaro similarity is increased for strings having a common prefix.
+// The prefix_scale must not be greater than 0.25 (the default is 0.1)
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct JaroWinklerMetric {
+    pub prefix_scale: f64,
+}
+
+impl Metric for JaroWinklerMetric {
+    fn similarity(self, s1: &str, s2: &str) -> f64 {
+        let jaro = jaro_similarity(s1, s2);
+        let prefix = s1
+            .chars()
+            .zip(s2.chars())
+            .take_while(|(c1, c2)| c1 == c2)
+            .take(4)
+            .count();
+        jaro + prefix as f64 * self.prefix_scale * (1.0 - jaro)
+    }
+}
+
+pub fn jaro_winkler_metric() -> JaroWinklerMetric {
+    JaroWinklerMetric { prefix_scale: 0.1 }
+}
+
+// Return the Levenshtein distance between 2 strings
+pub fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    distance(&chars(s1), &chars(s2))
+}
+
+// Return the Damerau distance between 2 strings, where swapping 2 adjacent chars counts as 1 edit
+pub fn damerau_distance(s1: &str, s2: &str) -> usize {
+    let (cs1, cs2) = (chars(s1), chars(s2));
+    last_cost(&create_damerau_edit_matrix(
+        &levenshtein_costs(),
+        &cs1,
+        &cs2,
+    ))
+}
+
+// Return the number of positions where 2 strings have different chars.
+// Returns None if the strings don't have the same length
+pub fn hamming_distance(s1: &str, s2: &str) -> Option<usize> {
+    if s1.chars().count() == s2.chars().count() {
+        Some(
+            s1.chars()
+                .zip(s2.chars())
+                .filter(|(c1, c2)| c1 != c2)
+                .count(),
+        )
+    } else {
+        None
+    }
+}
+
+// Return the Levenshtein distance between 2 strings, divided by the length of the longest string
+pub fn normalized_distance(s1: &str, s2: &str) -> f64 {
+    levenshtein_metric().normalized_distance(s1, s2)
+}
+
+// Return 1.0 minus the normalized Levenshtein distance between 2 strings
+pub fn similarity(s1: &str, s2: &str) -> f64 {
+    levenshtein_metric().similarity(s1, s2)
+}
+
+// Return the Jaro similarity between 2 strings
+pub fn jaro_similarity(s1: &str, s2: &str) -> f64 {
+    let (cs1, cs2) = (chars(s1), chars(s2));
+    if cs1.is_empty() && cs2.is_empty() {
+        return 1.0;
+    }
+    // 2 chars match if they are the same and not farther than this window
+    let window = (cs1.len().max(cs2.len()) / 2).saturating_sub(1);
+    let mut matched1 = vec![false; cs1.len()];
+    let mut matched2 = vec![false; cs2.len()];
+    let mut matches = 0;
+    for (i, c1) in cs1.iter().enumerate() {
+        let end = (i + window + 1).min(cs2.len());
+        for j in i.saturating_sub(window)..end {
+            if !matched2[j] && cs2[j] == *c1 {
+                matched1[i] = true;
+                matched2[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    // count the matching chars which are not in the same order
+    let matching1 = cs1.iter().zip(matched1).filter(|(_, m)| *m);
+    let matching2 = cs2.iter().zip(matched2).filter(|(_, m)| *m);
+    let transpositions = matching1
+        .zip(matching2)
+        .filter(|((c1, _), (c2, _))| c1 != c2)
+        .count()
+        / 2;
+
+    let m = matches as f64;
+    (m / cs1.len() as f64 + m / cs2.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+// Return the Jaro-Winkler similarity between 2 strings with the default prefix scale
+pub fn jaro_winkler_similarity(s1: &str, s2: &str) -> f64 {
+    jaro_winkler_metric().similarity(s1, s2)
+}
+
+// Return 1.0 - distance / length of the longest string, or 1.0 for 2 empty strings
+fn normalize(distance: usize, s1: &str, s2: &str) -> f64 {
+    let max_length = s1.chars().count().max(s2.chars().count());
+    if max_length == 0 {
+        1.0
+    } else {
+        1.0 - distance as f64 / max_length as f64
+    }
+}
+
+// Return the cost stored in the last cell of an edit matrix
+fn last_cost(matrix: &Matrix<Cost>) -> usize {
+    matrix
+        .get_value(matrix.rows_nb - 1, matrix.cols_nb - 1)
+        .map(|c| c.cost())
+        .unwrap_or(0)
+}
+
+fn chars(s: &str) -> Vec<char> {
+    s.chars().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn round(f: f64) -> f64 {
+        (f * 1000.0).round() / 1000.0
+    }
+
+    #[test]
+    fn test_edit_distances() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", ""), 0);
+        assert_eq!(levenshtein_distance("teh", "the"), 2);
+        assert_eq!(damerau_distance("teh", "the"), 1);
+        assert_eq!(hamming_distance("karolin", "kathrin"), Some(3));
+        assert_eq!(hamming_distance("karolin", "karol"), None);
+    }
+
+    #[test]
+    fn test_normalized_scores() {
+        assert_eq!(round(normalized_distance("kitten", "sitting")), 0.429);
+        assert_eq!(round(similarity("kitten", "sitting")), 0.571);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", ""), 0.0);
+        assert_eq!(round(damerau_metric().similarity("teh", "the")), 0.667);
+        assert_eq!(hamming_metric().similarity("abcd", "abxd"), 0.75);
+        assert_eq!(hamming_metric().similarity("abcd", "ab"), 0.5);
+    }
+
+    #[test]
+    fn test_jaro() {
+        assert_eq!(round(jaro_similarity("MARTHA", "MARHTA")), 0.944);
+        assert_eq!(round(jaro_similarity("DIXON", "DICKSONX")), 0.767);
+        assert_eq!(jaro_similarity("", ""), 1.0);
+        assert_eq!(jaro_similarity("abc", ""), 0.0);
+        assert_eq!(jaro_similarity("abc", "xyz"), 0.0);
+        assert_eq!(round(jaro_winkler_similarity("MARTHA", "MARHTA")), 0.961);
+        assert_eq!(round(jaro_winkler_similarity("DIXON", "DICKSONX")), 0.813);
+    }
+
+    #[test]
+    fn test_swap_metrics() {
+        // the metrics can be swapped to rank candidates
+        fn best<M: Metric>(metric: M, word: &str, candidates: &[&str]) -> String {
+            let mut sorted = candidates.to_vec();
+            sorted.sort_by(|a, b| {
+                metric
+                    .similarity(word, b)
+                    .total_cmp(&metric.similarity(word, a))
+            });
+            sorted[0].to_string()
+        }
+        let candidates = ["that", "hte"];
+        assert_eq!(best(levenshtein_metric(), "the", &candidates), "that");
+        assert_eq!(best(damerau_metric(), "the", &candidates), "hte");
+        assert_eq!(best(jaro_winkler_metric(), "the", &candidates), "that");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_similarities_are_normalized(s1 in "[a-d]{0,10}", s2 in "[a-d]{0,10}") {
+            fn check<M: Metric>(metric: M, s1: &str, s2: &str) -> bool {
+                let score = metric.similarity(s1, s2);
+                (0.0..=1.0).contains(&score)
+                    && metric.similarity(s1, s1) == 1.0
+                    && round(score) == round(metric.similarity(s2, s1))
+            }
+            prop_assert!(check(levenshtein_metric(), &s1, &s2));
+            prop_assert!(check(damerau_metric(), &s1, &s2));
+            prop_assert!(check(hamming_metric(), &s1, &s2));
+            prop_assert!(check(jaro_metric(), &s1, &s2));
+            prop_assert!(check(jaro_winkler_metric(), &s1, &s2));
+        }
+    }
+}
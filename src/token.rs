@@ -2,21 +2,33 @@
 
 // A Token is used to enclose a piece of text to compare and delimiters showing where the text is different from another piece of text
 //   Start / End are markers for the beginning and end of that text
+//   Elided is the text displayed in place of the tokens removed when shortening some text
+//   Item refers to a piece of text, stored elsewhere, by its index. Like Kept, it counts for 1 when shortening
+//   DifferenceStart / DifferenceEnd are delimiters around a region of differences, displayed by a renderer
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     Kept(String),
     Delimiter(String),
+    Elided(String),
+    Item(usize),
+    DifferenceStart,
+    DifferenceEnd,
     Start,
     End,
 }
 
 use Token::*;
 
-// Show a Token by skipping Start/End if present
+// Show a Token by skipping Start/End if present.
+// Items and difference delimiters have no text of their own and are displayed by the caller
 pub fn show_token(t: &Token) -> String {
     match t {
         Kept(s) => s.clone(),
         Delimiter(s) => s.clone(),
+        Elided(s) => s.clone(),
+        Item(_) => String::new(),
+        DifferenceStart => String::new(),
+        DifferenceEnd => String::new(),
         Start => String::new(),
         End => String::new(),
     }